@@ -0,0 +1,596 @@
+use crate::error::{ParseError, ParseErrorKind};
+use crate::moves::{is_corner, is_throne, Direction, Move, Position};
+use crate::rules::Ruleset;
+use crate::types::{Board, Hnfen, Piece, Player, Rank};
+
+/// Largest board a `BitBoard` can hold, 121 squares fill most of a u128
+pub const MAX_BITBOARD_SIZE: usize = 11;
+
+/// Position with one bit per square for each kind of piece, bit `x + y * size` standing for
+/// the square with indices (x, y). Generates the same moves and captures as `Board`, but only
+/// holds boards up to `MAX_BITBOARD_SIZE`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct BitBoard {
+    pub black: u128,
+    /// White pieces, including the kings
+    pub white: u128,
+    pub kings: u128,
+    /// Commanders of both sides, also part of `black` or `white`
+    pub commanders: u128,
+    /// Knights of both sides, also part of `black` or `white`
+    pub knights: u128,
+    pub next: Player,
+    /// Rules the masks are computed from, see `set_rules`
+    rules: Ruleset,
+    pub ply: u32,
+    pub since_capture: u32,
+    size: usize,
+    masks: Masks,
+}
+
+/// Squares the rules of a board treat specially, computed once per `BitBoard`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Masks {
+    board: u128,
+    first_column: u128,
+    last_column: u128,
+    throne: u128,
+    /// Empty squares hostile to the king, see `Ruleset::is_hostile_to_king`
+    castles: u128,
+    /// Squares only the king may stop on
+    restricted: u128,
+    /// Squares only the king may move over
+    impassable: u128,
+    hostile: u128,
+    camps: u128,
+}
+
+impl Masks {
+    fn new(size: usize, rules: &Ruleset) -> Self {
+        let mut masks = Masks {
+            board: 0,
+            first_column: 0,
+            last_column: 0,
+            throne: 0,
+            castles: 0,
+            restricted: 0,
+            impassable: 0,
+            hostile: 0,
+            camps: 0,
+        };
+        for y in 0..size {
+            for x in 0..size {
+                let bit = 1 << (x + y * size);
+                masks.board |= bit;
+                if x == 0 {
+                    masks.first_column |= bit;
+                }
+                if x == size - 1 {
+                    masks.last_column |= bit;
+                }
+                let corner = is_corner(x, y, size);
+                let throne = is_throne(x, y, size);
+                if throne {
+                    masks.throne |= bit;
+                }
+                if rules.is_hostile_to_king(x, y, size) {
+                    masks.castles |= bit;
+                }
+                if rules.restricted_corners && corner
+                    || rules.restricted_throne && throne
+                    || rules.is_citadel(x, y, size)
+                {
+                    masks.restricted |= bit;
+                }
+                if !rules.throne_passable && throne {
+                    masks.impassable |= bit;
+                }
+                if rules.is_hostile(x, y, size) {
+                    masks.hostile |= bit;
+                }
+                if rules
+                    .camps
+                    .contains(&Position::from_sized_indices(x, y, size))
+                {
+                    masks.camps |= bit;
+                }
+            }
+        }
+        masks
+    }
+}
+
+impl BitBoard {
+    /// Converts `board`, `None` if it is larger than `MAX_BITBOARD_SIZE`
+    pub fn from_board(board: &Board) -> Option<Self> {
+        let size = board.size();
+        if size > MAX_BITBOARD_SIZE {
+            return None;
+        }
+        let mut bits = BitBoard {
+            black: 0,
+            white: 0,
+            kings: 0,
+            commanders: 0,
+            knights: 0,
+            next: board.next,
+            rules: board.rules.clone(),
+            ply: board.ply,
+            since_capture: board.since_capture,
+            size,
+            masks: Masks::new(size, &board.rules),
+        };
+        for (y, rank) in board.ranks.iter().enumerate() {
+            for (x, piece) in rank.fields.iter().enumerate() {
+                if let Some(piece) = piece {
+                    bits.put(1 << (x + y * size), *piece);
+                }
+            }
+        }
+        Some(bits)
+    }
+
+    pub fn to_board(&self) -> Board {
+        let ranks = (0..self.size)
+            .map(|y| Rank {
+                fields: (0..self.size)
+                    .map(|x| self.piece(1 << (x + y * self.size)))
+                    .collect(),
+            })
+            .collect();
+        let mut board = Board {
+            ranks,
+            next: self.next,
+            rules: self.rules.clone(),
+            ply: self.ply,
+            since_capture: self.since_capture,
+            zobrist_pieces: 0,
+        };
+        board.refresh_zobrist();
+        board
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        &self.rules
+    }
+
+    /// Replaces the rules, recomputing the squares they treat specially
+    pub fn set_rules(&mut self, rules: Ruleset) {
+        self.masks = Masks::new(self.size, &rules);
+        self.rules = rules;
+    }
+
+    /// Bit of the square at `pos`
+    pub fn bit(&self, pos: &Position) -> u128 {
+        let (x, y) = pos.to_sized_indices(self.size);
+        1 << (x + y * self.size)
+    }
+
+    /// Square of a single set bit
+    fn position(&self, bit: u128) -> Position {
+        let index = bit.trailing_zeros() as usize;
+        Position::from_sized_indices(index % self.size, index / self.size, self.size)
+    }
+
+    pub fn occupied(&self) -> u128 {
+        self.black | self.white
+    }
+
+    /// Pieces of `color`
+    pub fn side(&self, color: Player) -> u128 {
+        match color {
+            Player::Black => self.black,
+            Player::White => self.white,
+        }
+    }
+
+    /// Piece on the square of the single set bit
+    fn piece(&self, bit: u128) -> Option<Piece> {
+        let color = if self.black & bit != 0 {
+            Player::Black
+        } else if self.white & bit != 0 {
+            Player::White
+        } else {
+            return None;
+        };
+        Some(if self.kings & bit != 0 {
+            Piece::King
+        } else if self.commanders & bit != 0 {
+            Piece::Commander(color)
+        } else if self.knights & bit != 0 {
+            Piece::Knight(color)
+        } else {
+            Piece::Normal(color)
+        })
+    }
+
+    fn put(&mut self, bit: u128, piece: Piece) {
+        match piece.color() {
+            Player::Black => self.black |= bit,
+            Player::White => self.white |= bit,
+        }
+        match piece {
+            Piece::King => self.kings |= bit,
+            Piece::Commander(_) => self.commanders |= bit,
+            Piece::Knight(_) => self.knights |= bit,
+            Piece::Normal(_) => {}
+        }
+    }
+
+    fn remove(&mut self, bits: u128) {
+        self.black &= !bits;
+        self.white &= !bits;
+        self.kings &= !bits;
+        self.commanders &= !bits;
+        self.knights &= !bits;
+    }
+
+    /// Moves every bit one square in direction `dir`, dropping those that leave the board
+    fn shift(&self, bits: u128, dir: &Direction) -> u128 {
+        match dir.vector(1) {
+            (1, _) => (bits & !self.masks.last_column) << 1,
+            (-1, _) => (bits & !self.masks.first_column) >> 1,
+            (_, 1) => (bits << self.size) & self.masks.board,
+            _ => bits >> self.size,
+        }
+    }
+
+    /// Same moves as `moves::possible_moves`, though not in the same order
+    pub fn possible_moves(&self) -> Vec<Move> {
+        let mut moves = Vec::new();
+        let occupied = self.occupied();
+        let mut own = self.side(self.next);
+        while own != 0 {
+            let from = own & own.wrapping_neg();
+            own &= !from;
+            let piece = self.piece(from).unwrap();
+            let king = piece == Piece::King;
+            let may_use_camps = self.next == Player::Black && self.masks.camps & from != 0;
+            let blocked_camps = if may_use_camps { 0 } else { self.masks.camps };
+            let (restricted, impassable) = if king {
+                (0, 0)
+            } else {
+                (self.masks.restricted, self.masks.impassable)
+            };
+            for dir in Direction::card().iter() {
+                let mut to = from;
+                loop {
+                    to = self.shift(to, dir);
+                    if to == 0 {
+                        break;
+                    }
+                    if to & occupied != 0 {
+                        // Commanders and knights may jump over the first piece in their way
+                        let jump = self.shift(to, dir);
+                        let other = self.piece(to).unwrap();
+                        if self.shift(from, dir) == to
+                            && piece.may_jump_over(other)
+                            && jump & (occupied | blocked_camps | restricted) == 0
+                            && jump != 0
+                        {
+                            moves.push(self.mov(from, jump));
+                        }
+                        break;
+                    }
+                    if to & blocked_camps != 0 {
+                        break;
+                    }
+                    if to & restricted == 0 {
+                        moves.push(self.mov(from, to));
+                    }
+                    if to & impassable != 0 {
+                        break;
+                    }
+                }
+            }
+        }
+        moves
+    }
+
+    fn mov(&self, from: u128, to: u128) -> Move {
+        Move {
+            from: self.position(from),
+            to: self.position(to),
+        }
+    }
+
+    /// Applies `mov` like `Board::apply` and returns the squares of the captured pieces
+    pub fn apply(&mut self, mov: &Move) -> u128 {
+        let from = self.bit(&mov.from);
+        let to = self.bit(&mov.to);
+        let piece = match self.piece(from) {
+            Some(p) => p,
+            None => return 0,
+        };
+        let color = piece.color();
+        self.remove(from);
+        self.put(to, piece);
+
+        let mut captured = 0;
+        let piece_may_take = self.side(color.opposite()) & !self.kings;
+        if let Piece::Knight(_) = piece {
+            for dir in Direction::card().iter() {
+                let jumped = self.shift(from, dir);
+                if self.shift(jumped, dir) == to && jumped & piece_may_take != 0 {
+                    captured |= jumped;
+                }
+            }
+            self.remove(captured);
+        }
+        let enemies = self.side(color.opposite());
+        let disarmed = piece == Piece::King && !self.rules.king_armed.is_hammer();
+        if !disarmed {
+            for dir in Direction::card().iter() {
+                let other = self.shift(to, dir);
+                if other & enemies == 0 {
+                    continue;
+                }
+                if other & self.kings != 0 {
+                    if self.is_king_capture(other, dir) {
+                        captured |= other;
+                    }
+                    continue;
+                }
+                let opposite = self.shift(other, dir);
+                if opposite & self.captures_for(color) != 0
+                    || opposite & self.hostile_to(color.opposite()) != 0
+                {
+                    captured |= other;
+                }
+            }
+            // Shieldwalls are looked for once the single pieces are gone
+            self.remove(captured);
+            if self.rules.shieldwall {
+                let walls = self.shieldwalls(to, color);
+                self.remove(walls);
+                captured |= walls;
+            }
+        }
+
+        self.next = color.opposite();
+        self.ply += 1;
+        self.since_capture = if captured == 0 {
+            self.since_capture + 1
+        } else {
+            0
+        };
+        captured
+    }
+
+    /// Squares whose pieces take part in captures for `color`
+    fn captures_for(&self, color: Player) -> u128 {
+        let armed_king = color == Player::White && self.rules.king_armed.is_anvil();
+        match (color, armed_king) {
+            (Player::White, false) => self.white & !self.kings,
+            _ => self.side(color),
+        }
+    }
+
+    /// Squares replacing a capturing piece against pieces of `victim`, see
+    /// `Board::is_hostile_to`
+    fn hostile_to(&self, victim: Player) -> u128 {
+        let occupied_throne = match victim {
+            Player::Black => self.masks.throne,
+            Player::White => 0,
+        };
+        self.masks.hostile & (!self.occupied() | occupied_throne)
+    }
+
+    /// Whether the king on the square of `king` is captured, see `Board::is_king_capture`
+    fn is_king_capture(&self, king: u128, dir: &Direction) -> bool {
+        let mut near_throne = king & self.masks.throne != 0;
+        let mut hostile = [false; 4];
+        for (k, dir) in Direction::card().iter().enumerate() {
+            let neighbour = self.shift(king, dir);
+            if neighbour == 0 {
+                hostile[k] = self.rules.hostile_edge;
+                continue;
+            }
+            near_throne |= neighbour & self.masks.throne != 0;
+            hostile[k] = neighbour & (self.black | self.masks.castles & !self.occupied()) != 0;
+        }
+        let sides = if near_throne {
+            self.rules.king_capture_sides_near_throne
+        } else {
+            self.rules.king_capture_sides
+        };
+        match (sides, dir) {
+            (2, Direction::Up) | (2, Direction::Down) => hostile[0] && hostile[1],
+            (2, _) => hostile[2] && hostile[3],
+            (n, _) => hostile.iter().filter(|h| **h).count() >= n as usize,
+        }
+    }
+
+    /// Rows along the edge the piece of `color` on `at` brackets, see
+    /// `Board::capture_shieldwalls`
+    fn shieldwalls(&self, at: u128, color: Player) -> u128 {
+        let mut captured = 0;
+        let capturing = self.captures_for(color);
+        let enemies = self.side(color.opposite());
+        for inward in Direction::card().iter() {
+            // Only squares on the edge opposite to `inward` have a row along it
+            let outward = self.shift(at, &opposite(inward));
+            if outward != 0 {
+                continue;
+            }
+            for along in Direction::card().iter() {
+                if (along.vector(1).0 == 0) == (inward.vector(1).0 == 0) {
+                    // Not along the edge
+                    continue;
+                }
+                let mut row = 0;
+                let mut square = self.shift(at, along);
+                let bracketed = loop {
+                    if square == 0 {
+                        break false;
+                    }
+                    if square & enemies != 0 {
+                        if self.shift(square, inward) & capturing == 0 {
+                            break false;
+                        }
+                        row |= square;
+                    } else if square & self.occupied() != 0 {
+                        break square & capturing != 0;
+                    } else {
+                        break square & self.masks.hostile != 0;
+                    }
+                    square = self.shift(square, along);
+                };
+                if bracketed && row.count_ones() >= 2 {
+                    captured |= row & !self.kings;
+                }
+            }
+        }
+        captured
+    }
+}
+
+fn opposite(dir: &Direction) -> Direction {
+    match dir {
+        Direction::Up => Direction::Down,
+        Direction::Down => Direction::Up,
+        Direction::Left => Direction::Right,
+        Direction::Right => Direction::Left,
+    }
+}
+
+impl Hnfen for BitBoard {
+    fn as_hnfen(&self) -> String {
+        self.to_board().as_hnfen()
+    }
+
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
+        let board = Board::from_hnfen(hnfen)?;
+        BitBoard::from_board(&board)
+            .ok_or_else(|| ParseError::new(0, ParseErrorKind::BoardTooLarge(board.size())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::moves::possible_moves;
+    use std::collections::HashSet;
+
+    /// Plays up to 300 moves on both representations, picking every `stride`th generated
+    /// move, compares them after every move and returns the number of captured pieces
+    fn compare(board: &Board, stride: usize) -> u32 {
+        let mut board = board.clone();
+        let mut bits = BitBoard::from_board(&board).unwrap();
+        let mut captures = 0;
+        for ply in 0..300 {
+            assert_eq!(bits.to_board(), board);
+            let moves = possible_moves(&board);
+            let expected: HashSet<_> = moves.iter().cloned().collect();
+            let actual: HashSet<_> = bits.possible_moves().into_iter().collect();
+            assert_eq!(actual, expected, "{}", board.as_hnfen());
+            if moves.is_empty() || board.status().is_over() {
+                break;
+            }
+            let mov = &moves[ply * stride % moves.len()];
+            let outcome = board.apply(mov);
+            let captured = bits.apply(mov);
+            let taken: u128 = outcome.captured.iter().map(|(p, _)| bits.bit(p)).sum();
+            assert_eq!(captured, taken, "{} {}", board.as_hnfen(), mov);
+            captures += captured.count_ones();
+        }
+        captures
+    }
+
+    #[test]
+    fn conversion() {
+        let board = Board::default();
+        let bits = BitBoard::from_board(&board).unwrap();
+        assert_eq!(bits.black.count_ones(), 24);
+        assert_eq!(bits.white.count_ones(), 13);
+        assert_eq!(bits.to_board(), board);
+        let hnfen = "11/11/11/11/11/5C5/5n5/11/11/11/1K9 h 12 3 fetlar";
+        assert_eq!(BitBoard::from_hnfen(hnfen).unwrap().as_hnfen(), hnfen);
+        assert_eq!(
+            BitBoard::from_hnfen(crate::ALEA_EVANGELII_START_HNFEN),
+            Err(ParseError::new(0, ParseErrorKind::BoardTooLarge(19)))
+        );
+        assert_eq!(
+            BitBoard::from_hnfen(crate::ALEA_EVANGELII_START_HNFEN)
+                .unwrap_err()
+                .to_string(),
+            "board of 19 ranks is too large at byte 0"
+        );
+    }
+
+    #[test]
+    fn same_as_board() {
+        let starts = [
+            crate::DEFAULT_START_HNFEN,
+            crate::BRANDUBH_START_HNFEN,
+            crate::TABLUT_START_HNFEN,
+            crate::TAWLBWRDD_START_HNFEN,
+            "3ancna3/5a5/11/a4h4a/a3hNh3a/aa1hCKhh1aa/a3hhh3a/a4h4a/11/5a5/3aaaaa3 a",
+        ];
+        for start in starts.iter() {
+            let board = Board::from_hnfen(start).unwrap();
+            let captures: u32 = [3, 7, 11, 17, 29].iter().map(|s| compare(&board, *s)).sum();
+            assert!(captures > 0);
+        }
+
+        // An enterable throne only the king may cross
+        let rules = Ruleset {
+            restricted_throne: false,
+            throne_passable: false,
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with(crate::DEFAULT_START_HNFEN, rules).unwrap();
+        for stride in [3, 7, 11].iter() {
+            compare(&board, *stride);
+        }
+
+        // Citadels like those of Alea Evangelii on 11x11
+        let rules = Ruleset {
+            citadels: ["c3", "c9", "i3", "i9"]
+                .iter()
+                .map(|citadel| Position::from_hnfen(citadel).unwrap())
+                .collect(),
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with(crate::DEFAULT_START_HNFEN, rules).unwrap();
+        let captures: u32 = [3, 7, 11, 17].iter().map(|s| compare(&board, *s)).sum();
+        assert!(captures > 0);
+
+        // Masks follow the rules
+        let hnfen = "11/11/11/11/11/11/11/11/2a8/11/1h9 a";
+        let mut bits = BitBoard::from_hnfen(hnfen).unwrap();
+        assert_eq!(
+            bits.clone()
+                .apply(&Move::from_hnfen("c3c1").unwrap())
+                .count_ones(),
+            1
+        );
+        bits.set_rules(Ruleset {
+            hostile_corners: false,
+            ..Ruleset::default()
+        });
+        assert!(!bits.rules().hostile_corners);
+        assert_eq!(bits.apply(&Move::from_hnfen("c3c1").unwrap()), 0);
+        assert_eq!(bits.to_board().rules, *bits.rules());
+
+        // Two attackers only take the king on the axis of the move
+        let mut bits = BitBoard::from_hnfen("7/7/7/7/1aKa3/6a/7 a 0 0 brandubh 7x7").unwrap();
+        assert_eq!(bits.apply(&Move::from_hnfen("g2c2").unwrap()), 0);
+        let mut bits = BitBoard::from_hnfen("7/1aK3a/7/7/7/7/7 a 0 0 brandubh 7x7").unwrap();
+        assert_eq!(
+            bits.apply(&Move::from_hnfen("g6d6").unwrap()).count_ones(),
+            1
+        );
+
+        // Shieldwall along the bottom edge
+        let wall = "11/11/11/11/11/11/11/11/11/3aa6/2ahh3a2 a";
+        let mut bits = BitBoard::from_hnfen(wall).unwrap();
+        let mov = Move::from_hnfen("i1f1").unwrap();
+        assert_eq!(bits.apply(&mov).count_ones(), 2);
+    }
+}
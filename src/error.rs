@@ -0,0 +1,220 @@
+use std::fmt::Display;
+
+use crate::moves::{IllegalMove, Move, Position};
+use crate::types::{Board, Hnfen, Piece, Player, Rank};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ParseError {
+    /// Byte offset into the parsed string
+    pub offset: usize,
+    pub kind: ParseErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParseErrorKind {
+    /// Input ended where more was expected
+    Empty,
+    /// Character that has no meaning at this place
+    BadCharacter(char),
+    /// Rank describes more fields than the board has ranks
+    RankOverflow,
+    /// Rank describes only this many fields
+    RankUnderflow(usize),
+    /// Board has this many ranks, which is not an odd number from 5 to `MAX_SIZE`
+    WrongRankCount(usize),
+    BadSideToMove(String),
+    /// Column or rank of a position is not on the board
+    BadCoordinate,
+    /// Input continues after everything was parsed
+    UnexpectedToken(String),
+    /// Input ended before this closing character
+    Unterminated(char),
+    /// Ruleset name that isn't one of the presets
+    UnknownRuleset(String),
+    /// Move of a game record can't be played in its position
+    IllegalMove(IllegalMove),
+    /// Capture markers of a move don't match the pieces it takes
+    WrongCaptures,
+    /// Rule, given as `key:value`, that isn't supported
+    BadRule(String),
+    /// Number that doesn't fit its field
+    BadNumber(String),
+    /// Board size, like `11x11`, that isn't supported
+    BadDimensions(String),
+    /// Board has this many ranks, which is valid but more than this representation holds
+    BoardTooLarge(usize),
+}
+
+impl ParseError {
+    pub fn new(offset: usize, kind: ParseErrorKind) -> Self {
+        ParseError { offset, kind }
+    }
+
+    /// Moves the error by `by` bytes, for errors of parsers working on substrings
+    pub fn shifted(self, by: usize) -> Self {
+        ParseError {
+            offset: self.offset + by,
+            ..self
+        }
+    }
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.kind {
+            ParseErrorKind::Empty => write!(f, "unexpected end of input"),
+            ParseErrorKind::BadCharacter(c) => write!(f, "unexpected character '{}'", c),
+            ParseErrorKind::RankOverflow => write!(f, "rank has too many fields"),
+            ParseErrorKind::RankUnderflow(n) => write!(f, "rank has only {} fields", n),
+            ParseErrorKind::WrongRankCount(n) => write!(f, "board can't have {} ranks", n),
+            ParseErrorKind::BadSideToMove(s) => write!(f, "unknown side to move \"{}\"", s),
+            ParseErrorKind::BadCoordinate => write!(f, "coordinate is not on the board"),
+            ParseErrorKind::UnexpectedToken(s) => write!(f, "unexpected \"{}\"", s),
+            ParseErrorKind::Unterminated(c) => write!(f, "missing '{}'", c),
+            ParseErrorKind::UnknownRuleset(s) => write!(f, "unknown ruleset \"{}\"", s),
+            ParseErrorKind::IllegalMove(e) => write!(f, "illegal move, {}", e),
+            ParseErrorKind::WrongCaptures => write!(f, "captures don't match the move"),
+            ParseErrorKind::BadRule(s) => write!(f, "unsupported rule \"{}\"", s),
+            ParseErrorKind::BadNumber(s) => write!(f, "number \"{}\" is out of range", s),
+            ParseErrorKind::BadDimensions(s) => write!(f, "unsupported board size \"{}\"", s),
+            ParseErrorKind::BoardTooLarge(n) => write!(f, "board of {} ranks is too large", n),
+        }?;
+        write!(f, " at byte {}", self.offset)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+macro_rules! impl_from_str {
+    ($($t:ty),*) => {
+        $(
+            impl std::str::FromStr for $t {
+                type Err = ParseError;
+
+                fn from_str(s: &str) -> Result<Self, Self::Err> {
+                    <$t as Hnfen>::from_hnfen(s)
+                }
+            }
+
+            impl std::convert::TryFrom<&str> for $t {
+                type Error = ParseError;
+
+                fn try_from(s: &str) -> Result<Self, Self::Error> {
+                    <$t as Hnfen>::from_hnfen(s)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_str!(Player, Piece, Rank, Board, Position, Move);
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::convert::TryFrom;
+
+    fn err(offset: usize, kind: ParseErrorKind) -> ParseError {
+        ParseError::new(offset, kind)
+    }
+
+    #[test]
+    fn board_errors() {
+        use ParseErrorKind::*;
+        assert_eq!(Board::from_hnfen(""), Err(err(0, Empty)));
+        assert_eq!(Board::from_hnfen("  "), Err(err(2, Empty)));
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11"),
+            Err(err(0, WrongRankCount(10)))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11/11"),
+            Err(err(0, WrongRankCount(12)))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/5x5"),
+            Err(err(31, BadCharacter('x')))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/12/11/11/11/11/11/11/11"),
+            Err(err(9, RankOverflow))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/a9a1/11/11/11/11/11"),
+            Err(err(18, RankOverflow))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/10/11/11/11/11/11/11/11"),
+            Err(err(11, RankUnderflow(10)))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 K"),
+            Err(err(33, BadSideToMove("K".to_string())))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 a a"),
+            Err(err(35, BadCharacter('a')))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 a 0 0 - 11x11 a"),
+            Err(err(47, UnexpectedToken("a".to_string())))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 a 99999999999"),
+            Err(err(35, BadNumber("99999999999".to_string())))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 a 0 0 shogi"),
+            Err(err(39, UnknownRuleset("shogi".to_string())))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 a 0 0 - 9x9"),
+            Err(err(41, BadDimensions("9x9".to_string())))
+        );
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/999999999999999999999999"),
+            Err(err(30, RankOverflow))
+        );
+    }
+
+    #[test]
+    fn move_errors() {
+        use ParseErrorKind::*;
+        assert_eq!(Move::from_hnfen(""), Err(err(0, Empty)));
+        assert_eq!(Move::from_hnfen("a11"), Err(err(3, Empty)));
+        assert_eq!(Move::from_hnfen("t11a1"), Err(err(0, BadCoordinate)));
+        assert_eq!(Move::from_hnfen("a20a1"), Err(err(1, BadCoordinate)));
+        assert_eq!(Move::from_hnfen("a11a0"), Err(err(4, BadCoordinate)));
+        assert_eq!(Move::from_hnfen("a11a"), Err(err(4, Empty)));
+        assert_eq!(Move::from_hnfen("a11a1-"), Err(err(5, BadCharacter('-'))));
+        assert_eq!(Move::from_hnfen("ä11a1"), Err(err(0, BadCoordinate)));
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/11 K")
+                .unwrap_err()
+                .to_string(),
+            "unknown side to move \"K\" at byte 33"
+        );
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(
+            crate::DEFAULT_START_HNFEN.parse::<Board>(),
+            Ok(Board::default())
+        );
+        assert_eq!("h".parse::<Player>(), Ok(Player::White));
+        assert_eq!(Piece::try_from("K"), Ok(Piece::King));
+        assert_eq!(
+            "a11b1".parse::<Move>().map(|m| m.to_string()),
+            Ok("a11b1".to_string())
+        );
+        assert_eq!(Position::try_from("k1"), Ok(Position::from_indices(10, 10)));
+        assert!("aa".parse::<Piece>().is_err());
+    }
+}
@@ -0,0 +1,205 @@
+use crate::history::History;
+use crate::moves::{IllegalMove, Move, MoveOutcome};
+use crate::rules::{Repetition, Ruleset};
+use crate::status::GameStatus;
+use crate::types::Board;
+use serde::{Deserialize, Serialize};
+
+/// A game from its starting position, with the moves played so far and those taken back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Game {
+    start: Board,
+    /// Played moves, followed by taken back moves `redo` can replay
+    moves: Vec<Move>,
+    /// Position after each move of `moves`
+    positions: Vec<Board>,
+    /// Number of moves currently played
+    ply: usize,
+    history: History,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new(Board::default())
+    }
+}
+
+impl Game {
+    /// Starts a game from `start`, played by the rules of that board
+    pub fn new(start: Board) -> Self {
+        Game {
+            history: History::new(&start),
+            start,
+            moves: Vec::new(),
+            positions: Vec::new(),
+            ply: 0,
+        }
+    }
+
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    pub fn rules(&self) -> &Ruleset {
+        &self.start.rules
+    }
+
+    /// Number of moves played, not counting taken back ones
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// Moves played, not counting taken back ones
+    pub fn moves(&self) -> &[Move] {
+        &self.moves[..self.ply]
+    }
+
+    /// Current position
+    pub fn board(&self) -> &Board {
+        self.position_at(self.ply).unwrap()
+    }
+
+    /// Position after `ply` moves, the start for 0, also for taken back moves
+    pub fn position_at(&self, ply: usize) -> Option<&Board> {
+        match ply {
+            0 => Some(&self.start),
+            _ => self.positions.get(ply - 1),
+        }
+    }
+
+    pub fn history(&self) -> &History {
+        &self.history
+    }
+
+    /// Plays `mov` in the current position and forgets the moves that were taken back
+    pub fn play(&mut self, mov: &Move) -> Result<MoveOutcome, IllegalMove> {
+        if self.result().is_over() {
+            return Err(IllegalMove::GameOver);
+        }
+        let mut board = self.board().clone();
+        let outcome = board.try_apply(mov)?;
+        if let Repetition::Forbidden(n) = board.rules.repetition {
+            if self.history.count(&board) + 1 >= n as usize {
+                return Err(IllegalMove::Repetition);
+            }
+        }
+        self.moves.truncate(self.ply);
+        self.positions.truncate(self.ply);
+        self.moves.push(mov.clone());
+        self.history.push(&board);
+        self.positions.push(board);
+        self.ply += 1;
+        Ok(outcome)
+    }
+
+    /// Takes back the latest move and returns it
+    pub fn undo(&mut self) -> Option<&Move> {
+        if self.ply == 0 {
+            return None;
+        }
+        self.ply -= 1;
+        self.history.pop();
+        self.moves.get(self.ply)
+    }
+
+    /// Replays the latest taken back move and returns it
+    pub fn redo(&mut self) -> Option<&Move> {
+        let board = self.positions.get(self.ply)?;
+        self.history.push(board);
+        self.ply += 1;
+        self.moves.get(self.ply - 1)
+    }
+
+    /// Status of the current position, including repetitions
+    pub fn result(&self) -> GameStatus {
+        self.history.status(self.board())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::moves::possible_moves;
+    use crate::status::WinReason;
+    use crate::types::{Hnfen, Player};
+
+    fn mov(hnfen: &str) -> Move {
+        Move::from_hnfen(hnfen).unwrap()
+    }
+
+    #[test]
+    fn play_undo_redo() {
+        let mut game = Game::default();
+        assert_eq!(game.ply(), 0);
+        assert_eq!(game.undo(), None);
+        assert_eq!(game.play(&mov("f1f1")), Err(IllegalMove::NoMovement));
+
+        game.play(&mov("d1d2")).unwrap();
+        game.play(&mov("f4e4")).unwrap();
+        assert_eq!(game.moves(), &[mov("d1d2"), mov("f4e4")]);
+        assert_eq!(game.board().next, Player::Black);
+        let second = game.board().clone();
+
+        assert_eq!(game.undo(), Some(&mov("f4e4")));
+        assert_eq!(game.undo(), Some(&mov("d1d2")));
+        assert_eq!(game.board(), &Board::default());
+        assert_eq!(game.redo(), Some(&mov("d1d2")));
+        assert_eq!(game.redo(), Some(&mov("f4e4")));
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.board(), &second);
+        assert_eq!(game.history().len(), 3);
+
+        game.undo();
+        game.play(&mov("f4g4")).unwrap();
+        assert_eq!(game.redo(), None);
+        assert_eq!(game.moves(), &[mov("d1d2"), mov("f4g4")]);
+    }
+
+    #[test]
+    fn position_at() {
+        let mut game = Game::default();
+        let mov = possible_moves(game.board())[0].clone();
+        game.play(&mov).unwrap();
+        let mut board = Board::default();
+        board.apply(&mov);
+        assert_eq!(game.position_at(0), Some(&Board::default()));
+        assert_eq!(game.position_at(1), Some(&board));
+        assert_eq!(game.position_at(2), None);
+        game.undo();
+        assert_eq!(game.position_at(1), Some(&board));
+    }
+
+    #[test]
+    fn result() {
+        let board = Board::from_hnfen("1a9/11/11/11/11/11/11/11/11/11/1K9 h").unwrap();
+        let mut game = Game::new(board);
+        assert_eq!(game.result(), GameStatus::Ongoing);
+        game.play(&mov("b1a1")).unwrap();
+        assert_eq!(
+            game.result(),
+            GameStatus::Won {
+                winner: Player::White,
+                reason: WinReason::KingEscaped
+            }
+        );
+        assert_eq!(game.play(&mov("a1b1")), Err(IllegalMove::GameOver));
+        game.undo();
+        assert!(!game.result().is_over());
+    }
+
+    #[test]
+    fn forbidden_repetition() {
+        let rules = Ruleset {
+            repetition: Repetition::Forbidden(2),
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with("1a9/11/11/11/11/5K5/11/11/11/11/11 a", rules).unwrap();
+        let mut game = Game::new(board);
+        for m in &["b11b10", "f6f7", "b10b11"] {
+            game.play(&mov(m)).unwrap();
+        }
+        assert_eq!(game.play(&mov("f7f6")), Err(IllegalMove::Repetition));
+        assert_eq!(game.ply(), 3);
+    }
+}
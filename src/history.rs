@@ -0,0 +1,172 @@
+use crate::moves::{possible_moves, Move};
+use crate::rules::Repetition;
+use crate::status::{GameStatus, WinReason};
+use crate::types::Board;
+use serde::{Deserialize, Serialize};
+
+/// Positions a game went through, used to apply the `Repetition` rule of the board
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct History {
+    /// Key of the position after each ply, starting with the initial position
+    keys: Vec<u64>,
+}
+
+/// Identifies the pieces on the board and the side to move, but not the rules. This is the
+/// Zobrist key `Board` keeps up to date.
+pub fn position_key(board: &Board) -> u64 {
+    board.zobrist()
+}
+
+impl History {
+    pub fn new(start: &Board) -> Self {
+        History {
+            keys: vec![position_key(start)],
+        }
+    }
+
+    /// Records the position after a move
+    pub fn push(&mut self, board: &Board) {
+        self.keys.push(position_key(board));
+    }
+
+    /// Forgets the latest position, for taking back a move
+    pub fn pop(&mut self) -> Option<u64> {
+        self.keys.pop()
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// How often the position of `board` occurred so far
+    pub fn count(&self, board: &Board) -> usize {
+        let key = position_key(board);
+        self.keys.iter().filter(|k| **k == key).count()
+    }
+
+    /// Moves of `possible_moves` that don't complete a forbidden repetition
+    pub fn legal_moves(&self, board: &Board) -> Vec<Move> {
+        let limit = match board.rules.repetition {
+            Repetition::Forbidden(n) => n as usize,
+            _ => return possible_moves(board),
+        };
+        let mut scratch = board.clone();
+        possible_moves(board)
+            .into_iter()
+            .filter(|mov| {
+                let outcome = scratch.apply(mov);
+                let repeats = self.count(&scratch) + 1 >= limit;
+                scratch.unapply(&outcome);
+                !repeats
+            })
+            .collect()
+    }
+
+    /// `Board::status` of the latest position `board`, taking repetitions into account
+    pub fn status(&self, board: &Board) -> GameStatus {
+        let status = board.status();
+        if status.is_over() {
+            return status;
+        }
+        let count = self.count(board);
+        let won = |winner| GameStatus::Won {
+            winner,
+            reason: WinReason::Repetition,
+        };
+        match board.rules.repetition {
+            Repetition::Allowed => status,
+            Repetition::Draw(n) if count >= n as usize => GameStatus::Drawn,
+            Repetition::LossForRepeater(n) if count >= n as usize => won(board.next),
+            Repetition::LossFor(loser, n) if count >= n as usize => won(loser.opposite()),
+            Repetition::Forbidden(_) if self.legal_moves(board).is_empty() => GameStatus::Won {
+                winner: board.next.opposite(),
+                reason: WinReason::NoMoves,
+            },
+            _ => status,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::rules::Ruleset;
+    use crate::types::{Hnfen, Player};
+
+    /// Black piece and the king step back and forth, every fourth ply repeats
+    const SHUFFLE: [&str; 4] = ["b11b10", "f6f7", "b10b11", "f7f6"];
+
+    fn shuffle(repetition: Repetition, plies: usize) -> (Board, History) {
+        let rules = Ruleset {
+            repetition,
+            ..Ruleset::default()
+        };
+        let mut board =
+            Board::from_hnfen_with("1a9/11/11/11/11/5K5/11/11/11/11/11 a", rules).unwrap();
+        let mut history = History::new(&board);
+        for mov in SHUFFLE.iter().cycle().take(plies) {
+            board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+            history.push(&board);
+        }
+        (board, history)
+    }
+
+    #[test]
+    fn count() {
+        let (board, mut history) = shuffle(Repetition::Allowed, 8);
+        assert_eq!(history.len(), 9);
+        assert_eq!(history.count(&board), 3);
+        assert_eq!(history.status(&board), GameStatus::Ongoing);
+        history.pop();
+        assert_eq!(history.count(&board), 2);
+    }
+
+    #[test]
+    fn copenhagen_repetition() {
+        let (board, history) = shuffle(Ruleset::copenhagen().repetition, 7);
+        assert_eq!(history.status(&board), GameStatus::Ongoing);
+        let (board, history) = shuffle(Ruleset::copenhagen().repetition, 8);
+        assert_eq!(
+            history.status(&board),
+            GameStatus::Won {
+                winner: Player::Black,
+                reason: WinReason::Repetition
+            }
+        );
+    }
+
+    #[test]
+    fn draw_and_repeater() {
+        let (board, history) = shuffle(Repetition::Draw(3), 8);
+        assert_eq!(history.status(&board), GameStatus::Drawn);
+
+        let (board, history) = shuffle(Repetition::LossForRepeater(3), 9);
+        assert_eq!(
+            history.status(&board),
+            GameStatus::Won {
+                winner: Player::White,
+                reason: WinReason::Repetition
+            }
+        );
+        let (board, history) = shuffle(Repetition::LossFor(Player::Black, 2), 4);
+        assert_eq!(history.status(&board).winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn forbidden() {
+        let (board, history) = shuffle(Repetition::Forbidden(3), 7);
+        let back = Move::from_hnfen("f7f6").unwrap();
+        assert!(possible_moves(&board).contains(&back));
+        assert!(!history.legal_moves(&board).contains(&back));
+        assert_eq!(
+            history.legal_moves(&board).len(),
+            possible_moves(&board).len() - 1
+        );
+        assert_eq!(history.status(&board), GameStatus::Ongoing);
+    }
+}
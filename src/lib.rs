@@ -1,6 +1,32 @@
+pub mod bitboard;
+pub mod error;
+pub mod game;
+pub mod history;
 pub mod moves;
+pub mod opentafl;
+pub mod record;
+pub mod rules;
+pub mod status;
 pub mod types;
+pub mod zobrist;
 
 /// Symbols for characters from https://hnefatafl.falch.dev/overview
 pub const DEFAULT_START_HNFEN: &str =
     "3aaaaa3/5a5/11/a4h4a/a3hhh3a/aa1hhKhh1aa/a3hhh3a/a4h4a/11/5a5/3aaaaa3 a";
+
+/// Brandubh on 7x7, the extended fields select its ruleset
+pub const BRANDUBH_START_HNFEN: &str = "3a3/3a3/3h3/aahKhaa/3h3/3a3/3a3 a 0 0 brandubh 7x7";
+
+/// Tablut on 9x9, the attackers start on the camps of `Ruleset::tablut`
+pub const TABLUT_START_HNFEN: &str =
+    "3aaa3/4a4/4h4/a3h3a/aahhKhhaa/a3h3a/4h4/4a4/3aaa3 a 0 0 tablut 9x9";
+
+/// Tawlbwrdd on 11x11, with as many pieces as `DEFAULT_START_HNFEN`: 24 attackers in four
+/// T shapes against 12 defenders in a diamond around the king
+pub const TAWLBWRDD_START_HNFEN: &str =
+    "4aaa4/4a1a4/5a5/5h5/aa2hhh2aa/a1ahhKhha1a/aa2hhh2aa/5h5/5a5/4a1a4/4aaa4 a 0 0 tawlbwrdd";
+
+/// Alea Evangelii on 19x19, 48 attackers against 24 defenders and the king
+pub const ALEA_EVANGELII_START_HNFEN: &str = "5a1a1a1a1a5/6a1a1a1a6/9a9/7a3a7/19/a8h8a/1a7h7a1/\
+    a2a3h1h1h3a2a/1a6hhh6a1/a1a2hhhhKhhhh2a1a/1a6hhh6a1/a2a3h1h1h3a2a/1a7h7a1/a8h8a/19/7a3a7/\
+    9a9/6a1a1a1a6/5a1a1a1a1a5 a 0 0 alea-evangelii 19x19";
@@ -1,7 +1,7 @@
 use std::fmt::Display;
 
-use crate::types::{Board, Hnfen, Piece};
-use regex::Regex;
+use crate::error::{ParseError, ParseErrorKind};
+use crate::types::{Board, Hnfen, Piece, Player, MAX_SIZE};
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -18,19 +18,77 @@ impl Display for Position {
 
 impl Position {
     pub fn from_indices(x: usize, y: usize) -> Self {
+        Position::from_sized_indices(x, y, 11)
+    }
+
+    /// Returns (x, y) tuple
+    pub fn to_indices(&self) -> (usize, usize) {
+        self.to_sized_indices(11)
+    }
+
+    /// Column x from the left and rank y from the top of a board with `size` ranks, counting
+    /// from 0
+    pub fn from_sized_indices(x: usize, y: usize, size: usize) -> Self {
         Position {
             column: (b'a' + x as u8) as char,
-            rank: 11 - y as u8,
+            rank: (size - y) as u8,
         }
     }
 
-    /// Returns (x, y) tuple
-    pub fn to_indices(&self) -> (usize, usize) {
+    /// Returns (x, y) tuple on a board with `size` ranks
+    pub fn to_sized_indices(&self, size: usize) -> (usize, usize) {
         (
-            (self.column as u8 - b'a') as usize,
-            (11 - self.rank) as usize,
+            (self.column as usize).wrapping_sub('a' as usize),
+            size.wrapping_sub(self.rank as usize),
         )
     }
+
+    /// Parsed positions may point outside of the board, e.g. `a12` on 11x11
+    pub fn is_on_board(&self, size: usize) -> bool {
+        let columns = 'a'..(b'a' + size as u8) as char;
+        columns.contains(&self.column) && (1..=size).contains(&(self.rank as usize))
+    }
+}
+
+impl Hnfen for Position {
+    fn as_hnfen(&self) -> String {
+        self.to_string()
+    }
+
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
+        let mut chars = hnfen.chars();
+        let column = chars
+            .next()
+            .ok_or_else(|| ParseError::new(0, ParseErrorKind::Empty))?;
+        if !column.is_ascii_lowercase() || (column as u8 - b'a') as usize >= MAX_SIZE {
+            return Err(ParseError::new(0, ParseErrorKind::BadCoordinate));
+        }
+        let digits = chars.as_str();
+        let digits_len = digits
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(digits.len());
+        let offset = column.len_utf8();
+        if digits_len == 0 {
+            return Err(match digits.chars().next() {
+                Some(c) => ParseError::new(offset, ParseErrorKind::BadCharacter(c)),
+                None => ParseError::new(offset, ParseErrorKind::Empty),
+            });
+        }
+        if let Some(c) = digits[digits_len..].chars().next() {
+            return Err(ParseError::new(
+                offset + digits_len,
+                ParseErrorKind::BadCharacter(c),
+            ));
+        }
+        let position = Position {
+            column,
+            rank: digits.parse().unwrap_or(0),
+        };
+        if !position.is_on_board(MAX_SIZE) {
+            return Err(ParseError::new(offset, ParseErrorKind::BadCoordinate));
+        }
+        Ok(position)
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
@@ -50,22 +108,82 @@ impl Hnfen for Move {
         format!("{}{}", self.from, self.to)
     }
 
-    fn from_hnfen(hnfen: &str) -> Option<Self> {
-        let move_re = Regex::new(r"^([a-k])(\d{1,2})([a-k])(\d{1,2})$").unwrap();
-        let cap = move_re.captures(hnfen).unwrap();
-        Some(Move {
-            from: Position {
-                column: cap.get(1)?.as_str().chars().next()?,
-                rank: cap.get(2)?.as_str().parse().ok()?,
-            },
-            to: Position {
-                column: cap.get(3)?.as_str().chars().next()?,
-                rank: cap.get(4)?.as_str().parse().ok()?,
-            },
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
+        // The source ends after the digits following its column
+        let from_len = hnfen
+            .char_indices()
+            .skip(1)
+            .find(|(_, c)| !c.is_ascii_digit())
+            .map(|(offset, _)| offset)
+            .unwrap_or_else(|| hnfen.len());
+        let (from, to) = hnfen.split_at(from_len);
+        Ok(Move {
+            from: Position::from_hnfen(from)?,
+            to: Position::from_hnfen(to).map_err(|e| e.shifted(from_len))?,
         })
     }
 }
 
+/// What happened when a move was applied to a board, doubles as the undo record for
+/// `Board::unapply`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MoveOutcome {
+    pub mov: Move,
+    /// Piece that moved, `None` if the source square was empty and nothing happened
+    pub piece: Option<Piece>,
+    /// Player that was on turn before this move
+    pub previous: Player,
+    /// Pieces removed from the board, with the square they were taken on
+    pub captured: Vec<(Position, Piece)>,
+    pub king_captured: bool,
+    pub king_escaped: bool,
+    /// Player to move after this move
+    pub next: Player,
+    /// `Board::since_capture` before this move
+    pub since_capture: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum IllegalMove {
+    /// Source or target is not on the board
+    OffBoard,
+    /// There is no piece on the source square
+    EmptySource,
+    /// The piece on the source square belongs to the player not on turn
+    WrongSide,
+    /// Source and target are the same square
+    NoMovement,
+    /// Source and target don't share a rank or column
+    Diagonal,
+    /// A piece stands on the target or between source and target
+    Blocked,
+    /// The ruleset reserves a square on the way or the target for the king
+    RestrictedSquare,
+    /// The move would complete a repetition the ruleset forbids
+    Repetition,
+    /// The game is already decided
+    GameOver,
+}
+
+impl Display for IllegalMove {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let reason = match self {
+            IllegalMove::OffBoard => "move leaves the board",
+            IllegalMove::EmptySource => "no piece on source square",
+            IllegalMove::WrongSide => "piece belongs to the other player",
+            IllegalMove::NoMovement => "piece does not move",
+            IllegalMove::Diagonal => "pieces only move along ranks and columns",
+            IllegalMove::Blocked => "path is blocked",
+            IllegalMove::RestrictedSquare => "only the king may use this square",
+            IllegalMove::Repetition => "move repeats a position too often",
+            IllegalMove::GameOver => "game is over",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
 pub enum Direction {
     Up,
     Down,
@@ -95,55 +213,126 @@ impl Direction {
     }
 }
 
-pub fn is_corner(x: usize, y: usize) -> bool {
-    matches!((x, y), (0, 0) | (0, 10) | (10, 0) | (10, 10))
+pub fn is_corner(x: usize, y: usize, size: usize) -> bool {
+    (x == 0 || x == size - 1) && (y == 0 || y == size - 1)
+}
+
+pub fn is_edge(x: usize, y: usize, size: usize) -> bool {
+    x == 0 || y == 0 || x == size - 1 || y == size - 1
+}
+
+pub fn is_throne(x: usize, y: usize, size: usize) -> bool {
+    (x, y) == (size / 2, size / 2)
 }
 
-pub fn is_castle(x: usize, y: usize) -> bool {
-    is_corner(x, y) || (x, y) == (5, 5)
+pub fn is_castle(x: usize, y: usize, size: usize) -> bool {
+    is_corner(x, y, size) || is_throne(x, y, size)
 }
 
-pub fn in_board(x: isize, y: isize) -> bool {
-    (0..11).contains(&x) && (0..11).contains(&y)
+pub fn in_board(x: isize, y: isize, size: usize) -> bool {
+    (0..size as isize).contains(&x) && (0..size as isize).contains(&y)
+}
+
+/// Checks `mov` against the same rules `possible_moves` generates moves from
+pub fn check_move(board: &Board, mov: &Move) -> Result<(), IllegalMove> {
+    let size = board.size();
+    if !mov.from.is_on_board(size) || !mov.to.is_on_board(size) {
+        return Err(IllegalMove::OffBoard);
+    }
+    let piece = board.get(&mov.from).ok_or(IllegalMove::EmptySource)?;
+    if piece.color() != board.next {
+        return Err(IllegalMove::WrongSide);
+    }
+
+    let (from_x, from_y) = mov.from.to_sized_indices(size);
+    let (to_x, to_y) = mov.to.to_sized_indices(size);
+    if (from_x, from_y) == (to_x, to_y) {
+        return Err(IllegalMove::NoMovement);
+    }
+    if from_x != to_x && from_y != to_y {
+        return Err(IllegalMove::Diagonal);
+    }
+
+    let length = from_x.max(to_x) - from_x.min(to_x) + from_y.max(to_y) - from_y.min(to_y);
+    let step = (
+        (to_x as isize - from_x as isize).signum(),
+        (to_y as isize - from_y as isize).signum(),
+    );
+    // Commanders and knights may jump over the piece next to them
+    let jump = board.jumped_over(mov, piece).is_some();
+    for k in 1..=length as isize {
+        if jump && k == 1 {
+            continue;
+        }
+        let x = (from_x as isize + step.0 * k) as usize;
+        let y = (from_y as isize + step.1 * k) as usize;
+        if board
+            .get(&Position::from_sized_indices(x, y, size))
+            .is_some()
+        {
+            return Err(IllegalMove::Blocked);
+        }
+        if k < length as isize && !board.rules.may_pass(piece, x, y, size)
+            || !board
+                .rules
+                .may_use_camp(piece, (from_x, from_y), x, y, size)
+        {
+            return Err(IllegalMove::RestrictedSquare);
+        }
+    }
+
+    if !board.rules.may_enter(piece, to_x, to_y, size) {
+        return Err(IllegalMove::RestrictedSquare);
+    }
+
+    Ok(())
 }
 
 pub fn possible_moves(board: &Board) -> Vec<Move> {
     let mut moves = Vec::new();
+    let size = board.size();
 
     let own_pieces = board.pieces(board.next);
     for own_location in own_pieces.iter() {
-        let (curr_x, curr_y) = own_location.to_indices();
+        let (curr_x, curr_y) = own_location.to_sized_indices(size);
         let (curr_x, curr_y) = (curr_x as isize, curr_y as isize);
-        let is_king = matches!(board.get(own_location), Some(Piece::King));
+        let piece = match board.get(own_location) {
+            Some(p) => p,
+            None => continue,
+        };
         for dir in Direction::card().iter() {
-            for length in 1..=10 {
+            for length in 1..size {
                 let (diff_x, diff_y) = dir.vector(length);
                 let new_x = curr_x + diff_x;
                 let new_y = curr_y + diff_y;
-                if !in_board(new_x, new_y) {
+                if !in_board(new_x, new_y, size) {
                     // Reached the edge of the board in this direction
                     break;
                 }
                 let (new_x, new_y) = (new_x as usize, new_y as usize);
-                if !is_king {
-                    if is_corner(new_x, new_y) {
-                        // Non-King cannot move onto corner
-                        break;
-                    }
-                    if (new_x, new_y) == (5, 5) {
-                        // Non-King cannot move onto center castle
-                        // But is allowed to move over!
-                        continue;
+                if let Some(other) = board.get(&Position::from_sized_indices(new_x, new_y, size)) {
+                    // Something is in the way, unless it can be jumped over
+                    if length == 1 && piece.may_jump_over(other) {
+                        moves.extend(jump(board, *own_location, piece, dir));
                     }
+                    break;
+                }
+                let from = (curr_x as usize, curr_y as usize);
+                if !board.rules.may_use_camp(piece, from, new_x, new_y, size) {
+                    // Camps can be neither entered nor crossed
+                    break;
+                }
+                // Restricted squares may still be moved over, and the throne may be entered
+                // without being passable
+                if board.rules.may_enter(piece, new_x, new_y, size) {
+                    moves.push(Move {
+                        from: *own_location,
+                        to: Position::from_sized_indices(new_x, new_y, size),
+                    })
                 }
-                if board.get(&Position::from_indices(new_x, new_y)).is_some() {
-                    // Something is in the way
+                if !board.rules.may_pass(piece, new_x, new_y, size) {
                     break;
                 }
-                moves.push(Move {
-                    from: *own_location,
-                    to: Position::from_indices(new_x, new_y),
-                })
             }
         }
     }
@@ -151,11 +340,34 @@ pub fn possible_moves(board: &Board) -> Vec<Move> {
     moves
 }
 
+/// The jump of `piece` from `from` over its neighbour in direction `dir`, if the square
+/// behind is free to enter
+fn jump(board: &Board, from: Position, piece: Piece, dir: &Direction) -> Option<Move> {
+    let size = board.size();
+    let (from_x, from_y) = from.to_sized_indices(size);
+    let (diff_x, diff_y) = dir.vector(2);
+    let (to_x, to_y) = (from_x as isize + diff_x, from_y as isize + diff_y);
+    if !in_board(to_x, to_y, size) {
+        return None;
+    }
+    let (to_x, to_y) = (to_x as usize, to_y as usize);
+    let to = Position::from_sized_indices(to_x, to_y, size);
+    if board.get(&to).is_some()
+        || !board
+            .rules
+            .may_use_camp(piece, (from_x, from_y), to_x, to_y, size)
+        || !board.rules.may_enter(piece, to_x, to_y, size)
+    {
+        return None;
+    }
+    Some(Move { from, to })
+}
+
 #[cfg(test)]
 mod tests {
 
     use super::*;
-    use crate::types::*;
+    use crate::rules::Ruleset;
 
     #[test]
     fn display() {
@@ -196,6 +408,11 @@ mod tests {
         assert_eq!(Position::from_indices(0, 0).to_indices(), (0, 0));
         assert_eq!(Position::from_indices(10, 10).to_indices(), (10, 10));
         assert_eq!(Position::from_indices(0, 10).to_indices(), (0, 10));
+        assert_eq!(Position::from_sized_indices(18, 0, 19).to_string(), "s19");
+        assert_eq!(Position::from_sized_indices(0, 6, 7).to_string(), "a1");
+        let c5 = Position::from_hnfen("c5").unwrap();
+        assert_eq!(c5.to_sized_indices(7), (2, 2));
+        assert_eq!(c5.to_sized_indices(19), (2, 14));
     }
 
     #[test]
@@ -227,6 +444,59 @@ mod tests {
         assert_eq!(possible_moves(&board).len(), 10);
     }
 
+    #[test]
+    fn check_moves() {
+        let board = Board::default();
+        let moves = possible_moves(&board);
+        for from in (0..121).map(|i| Position::from_indices(i % 11, i / 11)) {
+            for to in (0..121).map(|i| Position::from_indices(i % 11, i / 11)) {
+                let mov = Move { from, to };
+                assert_eq!(check_move(&board, &mov).is_ok(), moves.contains(&mov));
+            }
+        }
+
+        // An enterable throne nobody but the king may cross
+        let rules = Ruleset {
+            restricted_throne: false,
+            throne_passable: false,
+            ..Ruleset::default()
+        };
+        let throne = Board::from_hnfen_with("11/11/11/11/11/a10/11/11/11/11/5K5 a", rules).unwrap();
+        let throne_moves = possible_moves(&throne);
+        assert!(throne_moves.contains(&Move::from_hnfen("a6f6").unwrap()));
+        assert!(!throne_moves.contains(&Move::from_hnfen("a6g6").unwrap()));
+        for from in (0..121).map(|i| Position::from_indices(i % 11, i / 11)) {
+            for to in (0..121).map(|i| Position::from_indices(i % 11, i / 11)) {
+                let mov = Move { from, to };
+                assert_eq!(
+                    check_move(&throne, &mov).is_ok(),
+                    throne_moves.contains(&mov)
+                );
+            }
+        }
+
+        let check = |board: &Board, mov: &str| check_move(board, &Move::from_hnfen(mov).unwrap());
+        let off_board = Move {
+            from: Position::from_indices(3, 10),
+            to: Position {
+                column: 'd',
+                rank: 12,
+            },
+        };
+        assert_eq!(check_move(&board, &off_board), Err(IllegalMove::OffBoard));
+        assert_eq!(check(&board, "a10a9"), Err(IllegalMove::EmptySource));
+        assert_eq!(check(&board, "f5f4"), Err(IllegalMove::WrongSide));
+        assert_eq!(check(&board, "d11d11"), Err(IllegalMove::NoMovement));
+        assert_eq!(check(&board, "d11c10"), Err(IllegalMove::Diagonal));
+        assert_eq!(check(&board, "d11i11"), Err(IllegalMove::Blocked));
+        assert_eq!(check(&board, "a8a2"), Err(IllegalMove::Blocked));
+        assert_eq!(check(&board, "d11a11"), Err(IllegalMove::RestrictedSquare));
+
+        let board = Board::from_hnfen("11/11/11/11/11/a10/11/11/11/11/11").unwrap();
+        assert_eq!(check(&board, "a6f6"), Err(IllegalMove::RestrictedSquare));
+        assert_eq!(check(&board, "a6k6"), Ok(()));
+    }
+
     #[test]
     fn moves_hnfen() {
         let ex_move = Move {
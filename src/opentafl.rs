@@ -0,0 +1,536 @@
+//! Conversion from and to the notation used by OpenTafl: positions like
+//! `/3ttttt3/5t5/11/.../`, moves like `a4-a7xb7/c6`, `rules` strings and game files.
+//!
+//! Rules strings only carry what OpenTafl and `Ruleset` have in common, keys this crate doesn't
+//! know are skipped on import.
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::game::Game;
+use crate::moves::{Move, Position};
+use crate::record::Record;
+use crate::rules::{Escape, KingArmed, Repetition, Ruleset};
+use crate::types::{Board, Hnfen, Piece, Player};
+
+pub trait OpenTafl: Sized {
+    fn as_opentafl(&self) -> String;
+    fn from_opentafl(opentafl: &str) -> Result<Self, ParseError>;
+}
+
+impl OpenTafl for Piece {
+    fn as_opentafl(&self) -> String {
+        match self {
+            Piece::Normal(Player::Black) => "t",
+            Piece::Normal(Player::White) => "T",
+            Piece::King => "K",
+            Piece::Commander(Player::Black) => "c",
+            Piece::Commander(Player::White) => "C",
+            Piece::Knight(Player::Black) => "n",
+            Piece::Knight(Player::White) => "N",
+        }
+        .to_string()
+    }
+
+    fn from_opentafl(opentafl: &str) -> Result<Self, ParseError> {
+        let hnfen = to_hnfen(opentafl);
+        Piece::from_hnfen(&hnfen).map_err(|e| restore(e, opentafl, &hnfen))
+    }
+}
+
+/// Replaces the OpenTafl letters by hnfen ones and hides the hnfen letters, one character for
+/// each character of `opentafl`
+fn to_hnfen(opentafl: &str) -> String {
+    opentafl
+        .chars()
+        .map(|c| match c {
+            't' => 'a',
+            'T' => 'h',
+            'a' | 'h' => '?',
+            c if c.is_whitespace() => '?',
+            c => c,
+        })
+        .collect()
+}
+
+/// Moves an error of the hnfen parser on `hnfen`, the result of `to_hnfen`, to the same
+/// character of the OpenTafl input and puts that character back into it. Characters may have
+/// a different length in both.
+fn restore(error: ParseError, opentafl: &str, hnfen: &str) -> ParseError {
+    let index = hnfen
+        .get(..error.offset)
+        .map_or(0, |before| before.chars().count());
+    match opentafl.char_indices().nth(index) {
+        Some((offset, c)) => match error.kind {
+            ParseErrorKind::BadCharacter(_) => {
+                ParseError::new(offset, ParseErrorKind::BadCharacter(c))
+            }
+            kind => ParseError::new(offset, kind),
+        },
+        None => ParseError::new(opentafl.len(), error.kind),
+    }
+}
+
+impl OpenTafl for Board {
+    fn as_opentafl(&self) -> String {
+        let ranks: Vec<String> = self
+            .ranks
+            .iter()
+            .map(|rank| {
+                rank.as_hnfen()
+                    .replace(&Piece::Normal(Player::Black).as_hnfen(), "t")
+                    .replace(&Piece::Normal(Player::White).as_hnfen(), "T")
+            })
+            .collect();
+        format!("/{}/", ranks.join("/"))
+    }
+
+    /// OpenTafl positions don't say whose turn it is, the first player of the rules moves next
+    fn from_opentafl(opentafl: &str) -> Result<Self, ParseError> {
+        Board::from_opentafl_with(opentafl, Ruleset::default())
+    }
+}
+
+impl Board {
+    pub fn from_opentafl_with(opentafl: &str, rules: Ruleset) -> Result<Self, ParseError> {
+        let start = if opentafl.starts_with('/') { 1 } else { 0 };
+        let end = if opentafl.len() > start && opentafl.ends_with('/') {
+            opentafl.len() - 1
+        } else {
+            opentafl.len()
+        };
+        let ranks = &opentafl[start..end];
+        let hnfen = to_hnfen(ranks);
+        Board::from_hnfen_with(&hnfen, rules).map_err(|e| restore(e, ranks, &hnfen).shifted(start))
+    }
+}
+
+impl OpenTafl for Move {
+    fn as_opentafl(&self) -> String {
+        format!("{}-{}", self.from, self.to)
+    }
+
+    /// Captures after the target, like in `a4-a7xb7/c6`, are checked but not returned
+    fn from_opentafl(opentafl: &str) -> Result<Self, ParseError> {
+        Ok(parse_move(opentafl)?.0)
+    }
+}
+
+/// Parses a move with the squares it captures on, ignoring check marks like `+`
+fn parse_move(opentafl: &str) -> Result<(Move, Vec<Position>), ParseError> {
+    let opentafl = opentafl.trim_end_matches('+');
+    let (mov, captures) = match opentafl.find('x') {
+        Some(x) => (&opentafl[..x], Some((x + 1, &opentafl[x + 1..]))),
+        None => (opentafl, None),
+    };
+    let dash = mov.find('-').ok_or_else(|| {
+        let offset = mov.find(|c: char| !c.is_ascii_alphanumeric());
+        match offset.and_then(|o| mov[o..].chars().next().map(|c| (o, c))) {
+            Some((o, c)) => ParseError::new(o, ParseErrorKind::BadCharacter(c)),
+            None => ParseError::new(mov.len(), ParseErrorKind::Empty),
+        }
+    })?;
+    let mov = Move {
+        from: Position::from_hnfen(&mov[..dash])?,
+        to: Position::from_hnfen(&mov[dash + 1..]).map_err(|e| e.shifted(dash + 1))?,
+    };
+    let mut captured = Vec::new();
+    if let Some((mut offset, captures)) = captures {
+        for square in captures.split('/') {
+            captured.push(Position::from_hnfen(square).map_err(|e| e.shifted(offset))?);
+            offset += square.len() + 1;
+        }
+    }
+    Ok((mov, captured))
+}
+
+fn flag(value: bool) -> &'static str {
+    if value {
+        "y"
+    } else {
+        "n"
+    }
+}
+
+/// OpenTafl `rules` string of the rules and start position of `start`, with `atkf` giving the
+/// side to move in `start`, which OpenTafl positions don't carry. King strengths other than 2
+/// or 4 sides, escape squares besides corners and edge, forbidden repetitions, camps and
+/// citadels are written as the closest OpenTafl rule or left out.
+pub fn rules_as_opentafl(start: &Board) -> String {
+    let rules = &start.rules;
+    let mut tokens = vec![format!("dim:{}", start.size())];
+    if let Some(name) = rules.name() {
+        let mut chars = name.chars();
+        let first = chars.next().unwrap().to_ascii_uppercase();
+        tokens.push(format!("name:{}{}", first, chars.as_str()));
+    }
+    tokens.push(format!("atkf:{}", flag(start.next == Player::Black)));
+    match rules.escape {
+        Escape::Corners => tokens.push("esc:c".to_string()),
+        Escape::Edge => tokens.push("esc:e".to_string()),
+        Escape::Squares(_) => (),
+    }
+    let armed = match rules.king_armed {
+        KingArmed::Armed => "y",
+        KingArmed::Unarmed => "n",
+        KingArmed::Anvil => "a",
+        KingArmed::Hammer => "h",
+    };
+    tokens.push(format!("ka:{}", armed));
+    let strength = match (
+        rules.king_capture_sides,
+        rules.king_capture_sides_near_throne,
+    ) {
+        (sides, near) if sides != near => "c",
+        (sides, _) if sides <= 2 => "w",
+        _ => "s",
+    };
+    tokens.push(format!("ks:{}", strength));
+    tokens.push(format!("sw:{}", if rules.shieldwall { "s" } else { "n" }));
+    tokens.push(format!("efort:{}", flag(rules.exit_forts)));
+    tokens.push(format!("surf:{}", flag(rules.encirclement)));
+    match rules.repetition {
+        Repetition::Allowed => tokens.push("tfr:n".to_string()),
+        Repetition::Draw(_) => tokens.push("tfr:d".to_string()),
+        Repetition::LossForRepeater(_) | Repetition::LossFor(..) => {
+            tokens.push("tfr:l".to_string())
+        }
+        Repetition::Forbidden(_) => (),
+    }
+    tokens.push(format!("start:{}", start.as_opentafl()));
+    tokens.join(" ")
+}
+
+/// Start position with its rules from an OpenTafl `rules` string. Rules missing from the string
+/// are taken from the preset of the same name, or Copenhagen. `atkf` sets the side to move of
+/// the start, and the side to move first only for rules without a preset.
+pub fn rules_from_opentafl(opentafl: &str) -> Result<Board, ParseError> {
+    let offset_of = |token: &str| token.as_ptr() as usize - opentafl.as_ptr() as usize;
+    let mut pairs = Vec::new();
+    for token in opentafl.split_whitespace() {
+        let colon = token.find(':').ok_or_else(|| {
+            ParseError::new(
+                offset_of(token),
+                ParseErrorKind::UnexpectedToken(token.to_string()),
+            )
+        })?;
+        pairs.push((token, &token[..colon], &token[colon + 1..]));
+    }
+
+    let preset = pairs
+        .iter()
+        .find(|(_, key, _)| *key == "name")
+        .and_then(|(_, _, name)| Ruleset::from_name(&name.to_lowercase()));
+    let named = preset.is_some();
+    let mut rules = preset.unwrap_or_default();
+    let mut first = None;
+    let mut start = None;
+    let mut dim = None;
+    for (token, key, value) in pairs {
+        let bad = || ParseError::new(offset_of(token), ParseErrorKind::BadRule(token.to_string()));
+        let yes = || match value {
+            "y" => Ok(true),
+            "n" => Ok(false),
+            _ => Err(bad()),
+        };
+        match key {
+            "dim" => dim = Some((token, value)),
+            "atkf" => first = Some(if yes()? { Player::Black } else { Player::White }),
+            "esc" => {
+                rules.escape = match value {
+                    "c" => Escape::Corners,
+                    "e" => Escape::Edge,
+                    _ => return Err(bad()),
+                }
+            }
+            "ka" => {
+                rules.king_armed = match value {
+                    "y" => KingArmed::Armed,
+                    "n" => KingArmed::Unarmed,
+                    "a" => KingArmed::Anvil,
+                    "h" => KingArmed::Hammer,
+                    _ => return Err(bad()),
+                }
+            }
+            "ks" => {
+                let (sides, near) = match value {
+                    "s" => (4, 4),
+                    "w" => (2, 2),
+                    "c" => (2, 4),
+                    _ => return Err(bad()),
+                };
+                rules.king_capture_sides = sides;
+                rules.king_capture_sides_near_throne = near;
+            }
+            "sw" => {
+                rules.shieldwall = match value {
+                    "n" => false,
+                    "s" | "w" => true,
+                    _ => return Err(bad()),
+                }
+            }
+            "efort" => rules.exit_forts = yes()?,
+            "surf" => rules.encirclement = yes()?,
+            "tfr" => {
+                rules.repetition = match (value, rules.repetition) {
+                    ("n", _) => Repetition::Allowed,
+                    ("d", _) => Repetition::Draw(3),
+                    ("l", kept @ Repetition::LossFor(..)) => kept,
+                    ("l", _) => Repetition::LossForRepeater(3),
+                    _ => return Err(bad()),
+                }
+            }
+            "start" => start = Some(value),
+            _ => (),
+        }
+    }
+    if !named {
+        rules.first = first.unwrap_or(rules.first);
+    }
+    let next = first.unwrap_or(rules.first);
+    let mut board = match start {
+        Some(position) => Board::from_opentafl_with(position, rules)
+            .map_err(|e| e.shifted(offset_of(position)))?,
+        None => Board {
+            rules,
+            ..Board::default()
+        },
+    };
+    board.next = next;
+    match dim {
+        Some((token, value)) if value != board.size().to_string() => Err(ParseError::new(
+            offset_of(token),
+            ParseErrorKind::BadRule(token.to_string()),
+        )),
+        _ => Ok(board),
+    }
+}
+
+impl OpenTafl for Record {
+    /// OpenTafl game file with a tag per line, `rules` first, then the numbered moves. Comments
+    /// are not written.
+    fn as_opentafl(&self) -> String {
+        let mut buf = format!("[rules:{}]\n", rules_as_opentafl(self.game.start()));
+        for (name, value) in &self.tags {
+            buf.push_str(&format!("[{}:{}]\n", name, value));
+        }
+        for ply in 1..=self.game.ply() {
+            let (mov, captured) = self.capturing_move(ply);
+            if ply % 2 == 1 {
+                buf.push_str(&format!("{}. ", ply.div_ceil(2)));
+            } else {
+                buf.push(' ');
+            }
+            buf.push_str(&mov.as_opentafl());
+            let captured: Vec<String> = captured.iter().map(Position::as_hnfen).collect();
+            if !captured.is_empty() {
+                buf.push('x');
+                buf.push_str(&captured.join("/"));
+            }
+            if ply % 2 == 0 || ply == self.game.ply() {
+                buf.push('\n');
+            }
+        }
+        buf
+    }
+
+    fn from_opentafl(opentafl: &str) -> Result<Self, ParseError> {
+        let offset_of = |token: &str| token.as_ptr() as usize - opentafl.as_ptr() as usize;
+        let mut tags = Vec::new();
+        let mut start = None;
+        let mut game = None;
+        for line in opentafl.lines().map(str::trim) {
+            if line.starts_with('[') {
+                let close = line.len() - 1;
+                if !line.ends_with(']') {
+                    return Err(ParseError::new(
+                        offset_of(line) + line.len(),
+                        ParseErrorKind::Unterminated(']'),
+                    ));
+                }
+                let tag = &line[1..close];
+                let colon = tag.find(':').ok_or_else(|| {
+                    ParseError::new(
+                        offset_of(tag) + tag.len(),
+                        ParseErrorKind::Unterminated(':'),
+                    )
+                })?;
+                match &tag[..colon] {
+                    "rules" if game.is_none() => {
+                        let rules = &tag[colon + 1..];
+                        start = Some(
+                            rules_from_opentafl(rules).map_err(|e| e.shifted(offset_of(rules)))?,
+                        );
+                    }
+                    name => tags.push((name.to_string(), tag[colon + 1..].to_string())),
+                }
+                continue;
+            }
+
+            for token in line.split_whitespace() {
+                if token.ends_with('.')
+                    && token[..token.len() - 1].chars().all(|c| c.is_ascii_digit())
+                {
+                    continue;
+                }
+                let offset = offset_of(token);
+                let game = game.get_or_insert_with(|| Game::new(start.take().unwrap_or_default()));
+                let (mov, marked) = parse_move(token).map_err(|e| e.shifted(offset))?;
+                let outcome = game
+                    .play(&mov)
+                    .map_err(|e| ParseError::new(offset, ParseErrorKind::IllegalMove(e)))?;
+                let taken: Vec<Position> = outcome.captured.iter().map(|(pos, _)| *pos).collect();
+                if !marked.is_empty()
+                    && (taken.len() != marked.len() || !taken.iter().all(|p| marked.contains(p)))
+                {
+                    return Err(ParseError::new(offset, ParseErrorKind::WrongCaptures));
+                }
+            }
+        }
+
+        Ok(Record {
+            tags,
+            game: game.unwrap_or_else(|| Game::new(start.unwrap_or_default())),
+            comments: Vec::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const OPENTAFL_START: &str =
+        "/3ttttt3/5t5/11/t4T4t/t3TTT3t/tt1TTKTT1tt/t3TTT3t/t4T4t/11/5t5/3ttttt3/";
+
+    #[test]
+    fn position() {
+        assert_eq!(Board::default().as_opentafl(), OPENTAFL_START);
+        assert_eq!(Board::from_opentafl(OPENTAFL_START), Ok(Board::default()));
+        assert_eq!(
+            Board::from_opentafl(OPENTAFL_START.trim_matches('/')),
+            Ok(Board::default())
+        );
+        assert_eq!(
+            Board::from_opentafl("/3ttatt3/5t5/11/11/11/11/11/11/11/11/11/"),
+            Err(ParseError::new(4, ParseErrorKind::BadCharacter('a')))
+        );
+        assert_eq!(
+            Board::from_opentafl("/11/11/"),
+            Err(ParseError::new(1, ParseErrorKind::WrongRankCount(2)))
+        );
+        assert_eq!(Piece::from_opentafl("T"), Ok(Piece::Normal(Player::White)));
+        assert_eq!(Piece::from_opentafl("N"), Ok(Piece::Knight(Player::White)));
+        assert_eq!(Piece::Commander(Player::Black).as_opentafl(), "c");
+        assert!(Piece::from_opentafl("h").is_err());
+
+        // Offsets count bytes of the OpenTafl input, whatever the hnfen parser saw
+        assert_eq!(
+            Piece::from_opentafl("\u{a0}x"),
+            Err(ParseError::new(2, ParseErrorKind::BadCharacter('x')))
+        );
+        assert_eq!(
+            Piece::from_opentafl("tä"),
+            Err(ParseError::new(1, ParseErrorKind::BadCharacter('ä')))
+        );
+        let wide = "/3tt\u{3000}tt3/5t5/11/11/11/11/11/11/11/11/11/";
+        assert_eq!(
+            Board::from_opentafl(wide),
+            Err(ParseError::new(4, ParseErrorKind::BadCharacter('\u{3000}')))
+        );
+    }
+
+    #[test]
+    fn moves() {
+        let mov = Move::from_hnfen("a4a7").unwrap();
+        assert_eq!(mov.as_opentafl(), "a4-a7");
+        assert_eq!(Move::from_opentafl("a4-a7"), Ok(mov.clone()));
+        assert_eq!(Move::from_opentafl("a4-a7xb7/c6"), Ok(mov.clone()));
+        assert_eq!(Move::from_opentafl("a4-a7+"), Ok(mov));
+        assert_eq!(
+            Move::from_opentafl("a4a7"),
+            Err(ParseError::new(4, ParseErrorKind::Empty))
+        );
+        assert_eq!(
+            Move::from_opentafl("a4-a7xb7/t6"),
+            Err(ParseError::new(9, ParseErrorKind::BadCoordinate))
+        );
+    }
+
+    #[test]
+    fn rules() {
+        let board = Board::default();
+        let rules = rules_as_opentafl(&board);
+        assert_eq!(
+            rules,
+            format!(
+                "dim:11 name:Copenhagen atkf:y esc:c ka:y ks:s sw:s efort:y surf:y tfr:l start:{}",
+                OPENTAFL_START
+            )
+        );
+        assert_eq!(rules_from_opentafl(&rules), Ok(board));
+
+        let fetlar = Board::from_hnfen_with(crate::DEFAULT_START_HNFEN, Ruleset::fetlar()).unwrap();
+        assert_eq!(rules_from_opentafl(&rules_as_opentafl(&fetlar)), Ok(fetlar));
+
+        let board = rules_from_opentafl("atkf:n esc:e ks:w cenh:y").unwrap();
+        assert_eq!(board.next, Player::White);
+        assert_eq!(board.rules.escape, Escape::Edge);
+        assert_eq!(board.rules.king_capture_sides, 2);
+        assert_eq!(board.ranks, Board::default().ranks);
+
+        assert_eq!(
+            rules_from_opentafl("dim:9 esc:c"),
+            Err(ParseError::new(
+                0,
+                ParseErrorKind::BadRule("dim:9".to_string())
+            ))
+        );
+        assert_eq!(
+            rules_from_opentafl("esc:c ka:x"),
+            Err(ParseError::new(
+                6,
+                ParseErrorKind::BadRule("ka:x".to_string())
+            ))
+        );
+        assert_eq!(
+            rules_from_opentafl("esc:c start:/11/"),
+            Err(ParseError::new(13, ParseErrorKind::WrongRankCount(1)))
+        );
+    }
+
+    #[test]
+    fn game_file() {
+        let start = Board::from_hnfen("1a9/11/11/11/11/5K5/11/11/3ha6/11/2a8 a").unwrap();
+        let mut game = Game::new(start);
+        for mov in &["c1c3", "f6f7", "b11b10"] {
+            game.play(&Move::from_hnfen(mov).unwrap()).unwrap();
+        }
+        let record = Record::new(game);
+        let file = record.as_opentafl();
+        assert!(file.ends_with("[Result:*]\n1. c1-c3xd3 f6-f7\n2. b11-b10\n"));
+        assert_eq!(Record::from_opentafl(&file), Ok(record));
+
+        // OpenTafl positions leave out the side to move
+        let start = Board::from_hnfen("1a9/11/11/11/11/5K5/11/11/3ha6/11/2a8 h").unwrap();
+        let mut game = Game::new(start);
+        game.play(&Move::from_hnfen("f6f7").unwrap()).unwrap();
+        let record = Record::new(game);
+        let file = record.as_opentafl();
+        assert!(file.contains(" atkf:n "));
+        assert!(file.contains(" name:Copenhagen "));
+        assert_eq!(Record::from_opentafl(&file), Ok(record));
+
+        let record = Record::from_opentafl("[rules:esc:e]\n1. d1-d2 f4-e4\n2. d2-d1").unwrap();
+        assert_eq!(record.game.ply(), 3);
+        assert_eq!(record.game.rules().escape, Escape::Edge);
+
+        let file = "[rules:esc:c]\n1. d1-d2 d2-d1";
+        assert_eq!(
+            Record::from_opentafl(file),
+            Err(ParseError::new(
+                23,
+                ParseErrorKind::IllegalMove(crate::moves::IllegalMove::WrongSide)
+            ))
+        );
+    }
+}
@@ -0,0 +1,634 @@
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::error::{ParseError, ParseErrorKind};
+use crate::game::Game;
+use crate::moves::{Move, Position};
+use crate::rules::{Escape, KingArmed, Repetition, Ruleset};
+use crate::status::GameStatus;
+use crate::types::{Board, Hnfen, Player};
+use serde::{Deserialize, Serialize};
+
+/// A game with tag pairs and comments, written much like PGN:
+///
+/// ```text
+/// [Ruleset "copenhagen"]
+/// [Start "1a9/11/11/11/11/5K5/11/11/3ha6/11/2a8 a"]
+/// [Black "Alice"]
+/// [Result "*"]
+///
+/// {before the first move}
+/// 1. c1c3xd3 f6f7
+/// 2. b11b10 {after the third ply} 2... f7f8
+/// ```
+///
+/// Moves are in hnfen, each followed by an `x` and the square of every piece it takes.
+/// Tag values escape `"` and comments escape `}` with a backslash. Rules that aren't a preset
+/// are written as a `Rules` tag of `key:value` pairs instead of `Ruleset`, see `rules_tag`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    /// Tag pairs besides `Ruleset`, `Rules` and `Start`, which are taken from the game
+    pub tags: Vec<(String, String)>,
+    /// Game with the moves of the record, taken back moves are not written
+    pub game: Game,
+    /// Comments with the ply they follow, 0 for comments before the first move
+    pub comments: Vec<(usize, String)>,
+}
+
+impl Record {
+    /// Record of `game`, tagged with its result
+    pub fn new(game: Game) -> Self {
+        let result = result_tag(&game.result()).to_string();
+        Record {
+            tags: vec![("Result".to_string(), result)],
+            game,
+            comments: Vec::new(),
+        }
+    }
+
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag, _)| tag == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Replaces the value of the tag `name`, adding it if missing. Fails for names the reader
+    /// doesn't accept, and for `Ruleset`, `Rules` and `Start`, which are taken from the game.
+    pub fn set_tag(&mut self, name: &str, value: &str) -> Result<(), ParseError> {
+        if let Some((i, c)) = name.char_indices().find(|(_, c)| !is_tag_char(*c)) {
+            return Err(ParseError::new(i, ParseErrorKind::BadCharacter(c)));
+        }
+        match name {
+            "" => return Err(ParseError::new(0, ParseErrorKind::Empty)),
+            "Ruleset" | "Rules" | "Start" => {
+                return Err(ParseError::new(
+                    0,
+                    ParseErrorKind::UnexpectedToken(name.to_string()),
+                ))
+            }
+            _ => (),
+        }
+        match self.tags.iter_mut().find(|(tag, _)| tag == name) {
+            Some((_, old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+        Ok(())
+    }
+
+    /// Move of `ply` with the squares of the pieces it took
+    pub(crate) fn capturing_move(&self, ply: usize) -> (Move, Vec<Position>) {
+        let mov = self.game.moves()[ply - 1].clone();
+        let mut board = self.game.position_at(ply - 1).unwrap().clone();
+        let captured = board.apply(&mov).captured;
+        (mov, captured.into_iter().map(|(pos, _)| pos).collect())
+    }
+}
+
+/// Value of the `Result` tag: the winning player, "draw" or "*" for ongoing games
+pub fn result_tag(status: &GameStatus) -> &'static str {
+    match (status, status.winner()) {
+        (_, Some(Player::Black)) => "black",
+        (_, Some(Player::White)) => "white",
+        (GameStatus::Drawn, None) => "draw",
+        _ => "*",
+    }
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_comment(comment: &str) -> String {
+    comment.replace('\\', "\\\\").replace('}', "\\}")
+}
+
+/// Value of the `Rules` tag, a `key:value` pair for every rule, like `first:a ksides:2`.
+/// Flags are `y` or `n`, lists of squares are separated by commas.
+fn rules_tag(rules: &Ruleset) -> String {
+    let flag = |on: bool| if on { "y" } else { "n" };
+    let squares = |squares: &[Position]| {
+        let squares: Vec<String> = squares.iter().map(|pos| pos.as_hnfen()).collect();
+        squares.join(",")
+    };
+    let armed = match rules.king_armed {
+        KingArmed::Unarmed => "n",
+        KingArmed::Hammer => "h",
+        KingArmed::Anvil => "a",
+        KingArmed::Armed => "y",
+    };
+    let escape = match &rules.escape {
+        Escape::Corners => "c".to_string(),
+        Escape::Edge => "e".to_string(),
+        Escape::Squares(list) => squares(list),
+    };
+    let repetition = match rules.repetition {
+        Repetition::Allowed => "n".to_string(),
+        Repetition::Draw(n) => format!("d{}", n),
+        Repetition::LossForRepeater(n) => format!("l{}", n),
+        Repetition::LossFor(player, n) => format!("l{}{}", player.as_hnfen(), n),
+        Repetition::Forbidden(n) => format!("f{}", n),
+    };
+    [
+        format!("first:{}", rules.first.as_hnfen()),
+        format!("rcorners:{}", flag(rules.restricted_corners)),
+        format!("rthrone:{}", flag(rules.restricted_throne)),
+        format!("pthrone:{}", flag(rules.throne_passable)),
+        format!("karmed:{}", armed),
+        format!("ksides:{}", rules.king_capture_sides),
+        format!("ksidesthrone:{}", rules.king_capture_sides_near_throne),
+        format!("hedge:{}", flag(rules.hostile_edge)),
+        format!("hcorners:{}", flag(rules.hostile_corners)),
+        format!("hthrone:{}", flag(rules.hostile_throne)),
+        format!("esc:{}", escape),
+        format!("sw:{}", flag(rules.shieldwall)),
+        format!("efort:{}", flag(rules.exit_forts)),
+        format!("surf:{}", flag(rules.encirclement)),
+        format!("rep:{}", repetition),
+        format!("camps:{}", squares(&rules.camps)),
+        format!("citadels:{}", squares(&rules.citadels)),
+    ]
+    .join(" ")
+}
+
+/// Rules from the value of a `Rules` tag, see `rules_tag`. Rules left out are taken from
+/// `rules`.
+fn parse_rules_tag(text: &str, mut rules: Ruleset) -> Result<Ruleset, ParseError> {
+    let offset_of = |token: &str| token.as_ptr() as usize - text.as_ptr() as usize;
+    for token in text.split_whitespace() {
+        let bad = || ParseError::new(offset_of(token), ParseErrorKind::BadRule(token.to_string()));
+        let (key, value) = match token.find(':') {
+            Some(colon) => (&token[..colon], &token[colon + 1..]),
+            None => return Err(bad()),
+        };
+        let flag = || match value {
+            "y" => Ok(true),
+            "n" => Ok(false),
+            _ => Err(bad()),
+        };
+        let number = |value: &str| value.parse::<u8>().map_err(|_| bad());
+        let squares = || {
+            value
+                .split(',')
+                .filter(|square| !square.is_empty())
+                .map(|square| Position::from_hnfen(square).map_err(|_| bad()))
+                .collect::<Result<Vec<Position>, ParseError>>()
+        };
+        match key {
+            "first" => rules.first = Player::from_hnfen(value).map_err(|_| bad())?,
+            "rcorners" => rules.restricted_corners = flag()?,
+            "rthrone" => rules.restricted_throne = flag()?,
+            "pthrone" => rules.throne_passable = flag()?,
+            "karmed" => {
+                rules.king_armed = match value {
+                    "n" => KingArmed::Unarmed,
+                    "h" => KingArmed::Hammer,
+                    "a" => KingArmed::Anvil,
+                    "y" => KingArmed::Armed,
+                    _ => return Err(bad()),
+                }
+            }
+            "ksides" => rules.king_capture_sides = number(value)?,
+            "ksidesthrone" => rules.king_capture_sides_near_throne = number(value)?,
+            "hedge" => rules.hostile_edge = flag()?,
+            "hcorners" => rules.hostile_corners = flag()?,
+            "hthrone" => rules.hostile_throne = flag()?,
+            "esc" => {
+                rules.escape = match value {
+                    "c" => Escape::Corners,
+                    "e" => Escape::Edge,
+                    _ => Escape::Squares(squares()?),
+                }
+            }
+            "sw" => rules.shieldwall = flag()?,
+            "efort" => rules.exit_forts = flag()?,
+            "surf" => rules.encirclement = flag()?,
+            "rep" => {
+                let times = value.trim_start_matches(|c: char| !c.is_ascii_digit());
+                let kind = &value[..value.len() - times.len()];
+                rules.repetition = match kind {
+                    "n" if times.is_empty() => Repetition::Allowed,
+                    "d" => Repetition::Draw(number(times)?),
+                    "l" => Repetition::LossForRepeater(number(times)?),
+                    "la" => Repetition::LossFor(Player::Black, number(times)?),
+                    "lh" => Repetition::LossFor(Player::White, number(times)?),
+                    "f" => Repetition::Forbidden(number(times)?),
+                    _ => return Err(bad()),
+                }
+            }
+            "camps" => rules.camps = squares()?,
+            "citadels" => rules.citadels = squares()?,
+            _ => return Err(bad()),
+        }
+    }
+    Ok(rules)
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl Display for Record {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.game.rules().name() {
+            Some(name) => writeln!(f, "[Ruleset \"{}\"]", name)?,
+            None => writeln!(f, "[Rules \"{}\"]", rules_tag(self.game.rules()))?,
+        }
+        writeln!(f, "[Start \"{}\"]", self.game.start().as_hnfen())?;
+        for (name, value) in &self.tags {
+            writeln!(f, "[{} \"{}\"]", name, escape(value))?;
+        }
+        writeln!(f)?;
+
+        let comments_after = |ply| {
+            self.comments
+                .iter()
+                .filter(move |(after, _)| *after == ply)
+                .map(|(_, comment)| format!("{{{}}}", escape_comment(comment)))
+        };
+        for comment in comments_after(0) {
+            writeln!(f, "{}", comment)?;
+        }
+        let mut line: Vec<String> = Vec::new();
+        for ply in 1..=self.game.ply() {
+            let number = ply.div_ceil(2);
+            if ply % 2 == 1 {
+                if !line.is_empty() {
+                    writeln!(f, "{}", line.join(" "))?;
+                    line.clear();
+                }
+                line.push(format!("{}.", number));
+            } else if line.last().is_some_and(|token| token.starts_with('{')) {
+                line.push(format!("{}...", number));
+            }
+            let (mov, captured) = self.capturing_move(ply);
+            let mut token = mov.as_hnfen();
+            for pos in captured {
+                token.push('x');
+                token.push_str(&pos.as_hnfen());
+            }
+            line.push(token);
+            line.extend(comments_after(ply));
+        }
+        if !line.is_empty() {
+            writeln!(f, "{}", line.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the tag pair at the start of `text[offset..]`, returns name, value, offset of the
+/// value and offset after the tag
+fn parse_tag(text: &str, offset: usize) -> Result<(String, String, usize, usize), ParseError> {
+    let rest = &text[offset + 1..];
+    let name_len = rest.find(|c| !is_tag_char(c)).unwrap_or(rest.len());
+    let name = rest[..name_len].to_string();
+    let after_name = &rest[name_len..];
+    let quote = offset + 1 + name_len + (after_name.len() - after_name.trim_start().len());
+    match text[quote..].chars().next() {
+        Some('"') if !name.is_empty() => (),
+        Some(c) => return Err(ParseError::new(quote, ParseErrorKind::BadCharacter(c))),
+        None => return Err(ParseError::new(quote, ParseErrorKind::Empty)),
+    }
+
+    let mut value = String::new();
+    let mut chars = text[quote + 1..].char_indices();
+    let end = loop {
+        match chars.next() {
+            Some((_, '\\')) => value.extend(chars.next().map(|(_, c)| c)),
+            Some((i, '"')) => break quote + 1 + i + 1,
+            Some((_, c)) => value.push(c),
+            None => {
+                return Err(ParseError::new(
+                    text.len(),
+                    ParseErrorKind::Unterminated('"'),
+                ))
+            }
+        }
+    };
+    let close = end + (text[end..].len() - text[end..].trim_start().len());
+    match text[close..].chars().next() {
+        Some(']') => Ok((name, value, quote + 1, close + 1)),
+        Some(c) => Err(ParseError::new(close, ParseErrorKind::BadCharacter(c))),
+        None => Err(ParseError::new(close, ParseErrorKind::Unterminated(']'))),
+    }
+}
+
+/// Parses the comment at the start of `text[offset..]`, returns it and the offset after it
+fn parse_comment(text: &str, offset: usize) -> Result<(String, usize), ParseError> {
+    let mut comment = String::new();
+    let mut chars = text[offset + 1..].char_indices();
+    loop {
+        match chars.next() {
+            Some((_, '\\')) => comment.extend(chars.next().map(|(_, c)| c)),
+            Some((i, '}')) => return Ok((comment.trim().to_string(), offset + 1 + i + 1)),
+            Some((_, c)) => comment.push(c),
+            None => {
+                return Err(ParseError::new(
+                    text.len(),
+                    ParseErrorKind::Unterminated('}'),
+                ))
+            }
+        }
+    }
+}
+
+/// Parses a move with its capture markers, like `c1c3xd3`
+fn parse_move(token: &str) -> Result<(Move, Vec<Position>), ParseError> {
+    let mut parts = token.split('x');
+    let mov = Move::from_hnfen(parts.next().unwrap_or_default())?;
+    let mut offset = mov.as_hnfen().len() + 1;
+    let mut captured = Vec::new();
+    for part in parts {
+        captured.push(Position::from_hnfen(part).map_err(|e| e.shifted(offset))?);
+        offset += part.len() + 1;
+    }
+    Ok((mov, captured))
+}
+
+impl FromStr for Record {
+    type Err = ParseError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let skip_space = |offset: usize| text.len() - text[offset..].trim_start().len();
+
+        let mut offset = skip_space(0);
+        let mut tags = Vec::new();
+        let mut rules = None;
+        let mut custom_rules = None;
+        let mut start = None;
+        while text[offset..].starts_with('[') {
+            let (name, value, value_offset, end) = parse_tag(text, offset)?;
+            match name.as_str() {
+                "Ruleset" => rules = Some((value, value_offset)),
+                "Rules" => custom_rules = Some((value, value_offset)),
+                "Start" => start = Some((value, value_offset)),
+                _ => tags.push((name, value)),
+            }
+            offset = skip_space(end);
+        }
+
+        let rules = match rules {
+            Some((name, offset)) => Ruleset::from_name(&name)
+                .ok_or_else(|| ParseError::new(offset, ParseErrorKind::UnknownRuleset(name)))?,
+            None => Ruleset::default(),
+        };
+        let rules = match custom_rules {
+            Some((value, offset)) => {
+                parse_rules_tag(&value, rules).map_err(|e| e.shifted(offset))?
+            }
+            None => rules,
+        };
+        let (start, start_offset) = start.unwrap_or_else(|| (crate::DEFAULT_START_HNFEN.into(), 0));
+        let start = Board::from_hnfen_with(&start, rules).map_err(|e| e.shifted(start_offset))?;
+
+        let mut game = Game::new(start);
+        let mut comments = Vec::new();
+        while let Some(c) = text[offset..].chars().next() {
+            let rest = &text[offset..];
+            if c == '{' {
+                let (comment, end) = parse_comment(text, offset)?;
+                comments.push((game.ply(), comment));
+                offset = end;
+            } else if c.is_ascii_digit() {
+                // Move numbers only help the reader
+                let digits = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                let dots = rest[digits..]
+                    .find(|c| c != '.')
+                    .unwrap_or(rest.len() - digits);
+                match rest[digits..].chars().next() {
+                    Some('.') => offset += digits + dots,
+                    Some(c) => {
+                        return Err(ParseError::new(
+                            offset + digits,
+                            ParseErrorKind::BadCharacter(c),
+                        ))
+                    }
+                    None => return Err(ParseError::new(text.len(), ParseErrorKind::Empty)),
+                }
+            } else if c == '[' {
+                return Err(ParseError::new(
+                    offset,
+                    ParseErrorKind::UnexpectedToken("[".to_string()),
+                ));
+            } else {
+                let len = rest
+                    .find(|c: char| c.is_whitespace() || c == '{')
+                    .unwrap_or(rest.len());
+                let (mov, marked) = parse_move(&rest[..len]).map_err(|e| e.shifted(offset))?;
+                let outcome = game
+                    .play(&mov)
+                    .map_err(|e| ParseError::new(offset, ParseErrorKind::IllegalMove(e)))?;
+                let taken: Vec<Position> = outcome.captured.iter().map(|(pos, _)| *pos).collect();
+                if taken.len() != marked.len() || !taken.iter().all(|pos| marked.contains(pos)) {
+                    return Err(ParseError::new(offset, ParseErrorKind::WrongCaptures));
+                }
+                offset += len;
+            }
+            offset = skip_space(offset);
+        }
+
+        Ok(Record {
+            tags,
+            game,
+            comments,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::status::WinReason;
+
+    const START: &str = "1a9/11/11/11/11/5K5/11/11/3ha6/11/2a8 a";
+
+    fn record(moves: &[&str]) -> Record {
+        let rules = Ruleset::default();
+        let mut game = Game::new(Board::from_hnfen_with(START, rules).unwrap());
+        for mov in moves {
+            game.play(&Move::from_hnfen(mov).unwrap()).unwrap();
+        }
+        Record::new(game)
+    }
+
+    #[test]
+    fn write() {
+        let mut rec = record(&["c1c3", "f6f7", "b11b10", "f7f8"]);
+        rec.set_tag("Black", "Alice \"A\"").unwrap();
+        rec.comments.push((0, "before the first move".to_string()));
+        rec.comments.push((3, "after the third ply".to_string()));
+        assert_eq!(
+            rec.to_string(),
+            "[Ruleset \"copenhagen\"]\n\
+             [Start \"1a9/11/11/11/11/5K5/11/11/3ha6/11/2a8 a\"]\n\
+             [Result \"*\"]\n\
+             [Black \"Alice \\\"A\\\"\"]\n\
+             \n\
+             {before the first move}\n\
+             1. c1c3xd3 f6f7\n\
+             2. b11b10 {after the third ply} 2... f7f8\n"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut rec = record(&["c1c3", "f6f7", "b11b10"]);
+        rec.set_tag("Date", "2026.10.16").unwrap();
+        rec.comments.push((2, "king steps off".to_string()));
+        assert_eq!(rec.to_string().parse::<Record>(), Ok(rec.clone()));
+
+        let mut game = Game::default();
+        while game.ply() < 20 && !game.result().is_over() {
+            let mov = crate::moves::possible_moves(game.board())[0].clone();
+            game.play(&mov).unwrap();
+        }
+        let rec = Record::new(game);
+        let text = rec.to_string();
+        assert_eq!(text.parse::<Record>(), Ok(rec));
+        assert!(text.contains(&format!("[Start \"{}\"]", crate::DEFAULT_START_HNFEN)));
+    }
+
+    #[test]
+    fn custom_rules() {
+        let rules = Ruleset {
+            hostile_corners: false,
+            ..Ruleset::default()
+        };
+        let mut game = Game::new(Board::from_hnfen_with(START, rules).unwrap());
+        game.play(&Move::from_hnfen("c1c3").unwrap()).unwrap();
+        let rec = Record::new(game);
+        let text = rec.to_string();
+        assert!(text.starts_with("[Rules \"first:a "));
+        assert!(text.contains(" hcorners:n "));
+        assert_eq!(text.parse::<Record>(), Ok(rec));
+
+        let rules = Ruleset {
+            first: Player::White,
+            king_armed: KingArmed::Hammer,
+            king_capture_sides: 3,
+            escape: Escape::Squares(vec![Position::from_hnfen("a6").unwrap()]),
+            repetition: Repetition::LossFor(Player::Black, 4),
+            camps: vec![Position::from_hnfen("b2").unwrap()],
+            citadels: vec![
+                Position::from_hnfen("c3").unwrap(),
+                Position::from_hnfen("i9").unwrap(),
+            ],
+            ..Ruleset::tablut()
+        };
+        let game = Game::new(Board::from_hnfen_with(START, rules).unwrap());
+        let rec = Record::new(game);
+        assert_eq!(rec.to_string().parse::<Record>(), Ok(rec));
+
+        let rec: Record = "[Ruleset \"brandubh\"] [Rules \"sw:y\"] [Start \"7/7/7/3K3/7/7/7 a\"]"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            rec.game.rules(),
+            &Ruleset {
+                shieldwall: true,
+                ..Ruleset::brandubh()
+            }
+        );
+    }
+
+    #[test]
+    fn escaped_comments() {
+        let mut rec = record(&["c1c3", "f6f7"]);
+        rec.comments.push((0, "{braces}".to_string()));
+        rec.comments.push((1, "back\\slash \\} }".to_string()));
+        let text = rec.to_string();
+        assert!(text.contains("{{braces\\}}"));
+        assert!(text.contains("{back\\\\slash \\\\\\} \\}}"));
+        assert_eq!(text.parse::<Record>(), Ok(rec));
+    }
+
+    #[test]
+    fn tag_names() {
+        use ParseErrorKind::*;
+        let mut rec = record(&["c1c3"]);
+        assert_eq!(
+            rec.set_tag("White Player", "Bob"),
+            Err(ParseError::new(5, BadCharacter(' ')))
+        );
+        assert_eq!(rec.set_tag("", "Bob"), Err(ParseError::new(0, Empty)));
+        assert_eq!(
+            rec.set_tag("Start", "11/11 a"),
+            Err(ParseError::new(0, UnexpectedToken("Start".to_string())))
+        );
+        assert_eq!(
+            rec.set_tag("Rules", "sw:n"),
+            Err(ParseError::new(0, UnexpectedToken("Rules".to_string())))
+        );
+        assert_eq!(rec.tags, record(&["c1c3"]).tags);
+
+        rec.set_tag("White_Player", "Bob").unwrap();
+        rec.set_tag("Round2", "] \"x\"").unwrap();
+        assert_eq!(rec.to_string().parse::<Record>(), Ok(rec));
+    }
+
+    #[test]
+    fn read() {
+        let rec: Record = "[Ruleset \"fetlar\"]\n1. d1d2 {first} f4e4 2.d2d1"
+            .parse()
+            .unwrap();
+        assert_eq!(rec.game.rules(), &Ruleset::fetlar());
+        assert_eq!(rec.game.start().ranks, Board::default().ranks);
+        assert_eq!(rec.game.ply(), 3);
+        assert_eq!(rec.comments, vec![(1, "first".to_string())]);
+        assert_eq!(rec.tags, vec![]);
+
+        let rec: Record =
+            "[Start \"1a9/11/11/11/11/11/11/11/11/11/1K9 h\"] [Result \"white\"] b1a1"
+                .parse()
+                .unwrap();
+        assert_eq!(rec.tag("Result"), Some("white"));
+        assert_eq!(
+            rec.game.result(),
+            GameStatus::Won {
+                winner: Player::White,
+                reason: WinReason::KingEscaped
+            }
+        );
+    }
+
+    #[test]
+    fn errors() {
+        use crate::moves::IllegalMove;
+        use ParseErrorKind::*;
+        let err = |text: &str| text.parse::<Record>().unwrap_err();
+        assert_eq!(
+            err("[Ruleset \"shogi\"]"),
+            ParseError::new(10, UnknownRuleset("shogi".to_string()))
+        );
+        assert_eq!(err("[Start \"11\"]"), ParseError::new(8, WrongRankCount(1)));
+        assert_eq!(err("[Event \"x]"), ParseError::new(10, Unterminated('"')));
+        assert_eq!(err("[Event x]"), ParseError::new(7, BadCharacter('x')));
+        assert_eq!(err("1. d1d2 {"), ParseError::new(9, Unterminated('}')));
+        assert_eq!(err("1. d1d2 f4e4 d2d1x"), ParseError::new(18, Empty));
+        assert_eq!(
+            err("1. d1d2 d2d1"),
+            ParseError::new(8, IllegalMove(IllegalMove::WrongSide))
+        );
+        assert_eq!(
+            err(&format!("[Start \"{}\"] c1c3", START)),
+            ParseError::new(50, WrongCaptures)
+        );
+        assert_eq!(
+            err("1. d1d2 [Event \"x\"]"),
+            ParseError::new(8, UnexpectedToken("[".to_string()))
+        );
+        assert_eq!(err("1- d1d2"), ParseError::new(1, BadCharacter('-')));
+        assert_eq!(
+            err("[Rules \"sw:y ksides:x\"]"),
+            ParseError::new(13, BadRule("ksides:x".to_string()))
+        );
+        assert_eq!(
+            err("[Rules \"rep:q3\"]"),
+            ParseError::new(8, BadRule("rep:q3".to_string()))
+        );
+    }
+}
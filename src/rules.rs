@@ -0,0 +1,962 @@
+use crate::moves::{is_corner, is_edge, is_throne, Position};
+use crate::types::{Hnfen, Piece, Player};
+use serde::{Deserialize, Serialize};
+
+/// Name of a preset and the function building its rules
+type Preset = (&'static str, fn() -> Ruleset);
+
+/// Rulesets that can be referred to by name
+const PRESETS: [Preset; 6] = [
+    ("copenhagen", Ruleset::copenhagen),
+    ("fetlar", Ruleset::fetlar),
+    ("brandubh", Ruleset::brandubh),
+    ("tablut", Ruleset::tablut),
+    ("tawlbwrdd", Ruleset::tawlbwrdd),
+    ("alea-evangelii", Ruleset::alea_evangelii),
+];
+
+/// Rules of a tafl variant, carried by every `Board`
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Ruleset {
+    /// Side to move when a position doesn't say
+    pub first: Player,
+    /// Only the king may stop on a corner
+    pub restricted_corners: bool,
+    /// Only the king may stop on the throne
+    pub restricted_throne: bool,
+    /// Other pieces may move over the empty throne
+    pub throne_passable: bool,
+    /// How the king takes part in capturing attackers
+    pub king_armed: KingArmed,
+    /// Number of hostile neighbours needed to capture the king, 2 means two opposite sides
+    pub king_capture_sides: u8,
+    /// Like `king_capture_sides`, while the king is on or next to the throne
+    pub king_capture_sides_near_throne: u8,
+    /// The board edge counts as a hostile neighbour of the king
+    pub hostile_edge: bool,
+    /// Corners replace a capturing piece in ordinary captures, and count as hostile
+    /// neighbours of the king
+    pub hostile_corners: bool,
+    /// The throne replaces a capturing piece in ordinary captures, while empty for defenders
+    /// and always for attackers. The empty throne counts as a hostile neighbour of the king.
+    pub hostile_throne: bool,
+    pub escape: Escape,
+    /// Rows of two or more pieces along the edge can be captured together, bracketed by pieces
+    /// or hostile corners and blocked from the front
+    pub shieldwall: bool,
+    /// Defenders win with the king in an unbreakable fort on the edge
+    pub exit_forts: bool,
+    /// Attackers win by enclosing all defenders
+    pub encirclement: bool,
+    /// What happens when a position comes up again, see `History`
+    pub repetition: Repetition,
+    /// Squares only attackers starting their move on a camp may move onto or over
+    pub camps: Vec<Position>,
+    /// Squares besides corners and throne that only the king may stop on, and that replace a
+    /// capturing piece like corners while empty
+    pub citadels: Vec<Position>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum KingArmed {
+    /// King never captures
+    Unarmed,
+    /// King captures by moving, but doesn't help others capture
+    Hammer,
+    /// King helps others capture, but doesn't capture by moving
+    Anvil,
+    /// King captures like any other piece
+    Armed,
+}
+
+impl KingArmed {
+    /// Whether the king captures by moving next to an attacker
+    pub fn is_hammer(&self) -> bool {
+        matches!(self, KingArmed::Hammer | KingArmed::Armed)
+    }
+
+    /// Whether the king completes captures made by other defenders
+    pub fn is_anvil(&self) -> bool {
+        matches!(self, KingArmed::Anvil | KingArmed::Armed)
+    }
+}
+
+/// Each variant holds how often a position has to occur for the rule to apply
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Repetition {
+    /// Positions may repeat without consequence
+    Allowed,
+    /// Game is drawn
+    Draw(u8),
+    /// Player whose move repeated the position loses
+    LossForRepeater(u8),
+    /// This player loses, no matter who repeated the position
+    LossFor(Player, u8),
+    /// Moves that would repeat the position are not legal
+    Forbidden(u8),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Escape {
+    /// King wins on reaching a corner
+    Corners,
+    /// King wins on reaching any edge square
+    Edge,
+    /// King wins on reaching one of these squares
+    Squares(Vec<Position>),
+}
+
+/// Copenhagen rules, which `DEFAULT_START_HNFEN` is set up for
+impl Default for Ruleset {
+    fn default() -> Self {
+        Ruleset::copenhagen()
+    }
+}
+
+impl Ruleset {
+    /// Copenhagen Hnefatafl as played on https://hnefatafl.falch.dev, starting from
+    /// `DEFAULT_START_HNFEN`
+    pub fn copenhagen() -> Self {
+        Ruleset {
+            first: Player::Black,
+            restricted_corners: true,
+            restricted_throne: true,
+            throne_passable: true,
+            king_armed: KingArmed::Armed,
+            king_capture_sides: 4,
+            king_capture_sides_near_throne: 4,
+            hostile_edge: false,
+            hostile_corners: true,
+            hostile_throne: true,
+            escape: Escape::Corners,
+            shieldwall: true,
+            exit_forts: true,
+            encirclement: true,
+            repetition: Repetition::LossFor(Player::White, 3),
+            camps: Vec::new(),
+            citadels: Vec::new(),
+        }
+    }
+
+    /// Fetlar Hnefatafl, which captures like Copenhagen but has no shieldwalls, and neither
+    /// exit forts nor encirclement end the game. Starts from `DEFAULT_START_HNFEN`.
+    ///
+    /// Fetlar makes only the empty throne hostile, while Copenhagen's occupied throne stays
+    /// hostile to attackers. The king is armed in both, so an attacker next to the occupied
+    /// throne is taken against the king either way and the throne flags are the same.
+    pub fn fetlar() -> Self {
+        Ruleset {
+            shieldwall: false,
+            exit_forts: false,
+            encirclement: false,
+            ..Ruleset::copenhagen()
+        }
+    }
+
+    /// Brandubh, where the king away from the throne is taken between two attackers and
+    /// neither shieldwalls, exit forts nor encirclement exist. Starts from
+    /// `BRANDUBH_START_HNFEN` on 7x7.
+    pub fn brandubh() -> Self {
+        Ruleset {
+            king_capture_sides: 2,
+            king_capture_sides_near_throne: 4,
+            shieldwall: false,
+            exit_forts: false,
+            encirclement: false,
+            ..Ruleset::copenhagen()
+        }
+    }
+
+    /// Tablut as reconstructed from Linnaeus' notes. The king escapes to any edge square, is
+    /// taken between two attackers away from the throne, and nobody but the king may cross the
+    /// throne. Attackers start in camps no other piece may use. Starts from `TABLUT_START_HNFEN`
+    /// on 9x9.
+    pub fn tablut() -> Self {
+        let camps = [
+            "a4", "a5", "a6", "b5", "i4", "i5", "i6", "h5", "d1", "e1", "f1", "e2", "d9", "e9",
+            "f9", "e8",
+        ];
+        Ruleset {
+            restricted_corners: false,
+            throne_passable: false,
+            king_capture_sides: 2,
+            king_capture_sides_near_throne: 4,
+            hostile_corners: false,
+            escape: Escape::Edge,
+            shieldwall: false,
+            exit_forts: false,
+            encirclement: false,
+            camps: camps
+                .iter()
+                .map(|camp| Position::from_hnfen(camp).unwrap())
+                .collect(),
+            ..Ruleset::copenhagen()
+        }
+    }
+
+    /// Welsh Tawlbwrdd after Bell's reconstruction. The king escapes to any edge square and
+    /// is taken from four sides, corners are ordinary squares, and neither shieldwalls, exit
+    /// forts nor encirclement exist. Starts from `TAWLBWRDD_START_HNFEN` on 11x11.
+    pub fn tawlbwrdd() -> Self {
+        Ruleset {
+            restricted_corners: false,
+            hostile_corners: false,
+            escape: Escape::Edge,
+            shieldwall: false,
+            exit_forts: false,
+            encirclement: false,
+            ..Ruleset::copenhagen()
+        }
+    }
+
+    /// Alea Evangelii, reconstructed for 19x19 with corner escape. The king away from the
+    /// throne is taken between two attackers, there are neither shieldwalls nor exit forts, but
+    /// attackers still win by encirclement. Besides corners and throne, the manuscript marks
+    /// eight more squares, taken as citadels. Starts from `ALEA_EVANGELII_START_HNFEN`.
+    pub fn alea_evangelii() -> Self {
+        let citadels = ["c3", "c17", "q3", "q17", "g7", "g13", "m7", "m13"];
+        Ruleset {
+            king_capture_sides: 2,
+            king_capture_sides_near_throne: 4,
+            shieldwall: false,
+            exit_forts: false,
+            citadels: citadels
+                .iter()
+                .map(|citadel| Position::from_hnfen(citadel).unwrap())
+                .collect(),
+            ..Ruleset::copenhagen()
+        }
+    }
+
+    /// Name of the preset these rules equal, if any
+    pub fn name(&self) -> Option<&'static str> {
+        PRESETS
+            .iter()
+            .find(|(_, preset)| preset() == *self)
+            .map(|(name, _)| *name)
+    }
+
+    /// Preset called `name`, see `Ruleset::name`
+    pub fn from_name(name: &str) -> Option<Ruleset> {
+        PRESETS
+            .iter()
+            .find(|(preset, _)| *preset == name)
+            .map(|(_, rules)| rules())
+    }
+
+    /// Whether `piece` may end its move on (x, y)
+    pub fn may_enter(&self, piece: Piece, x: usize, y: usize, size: usize) -> bool {
+        piece == Piece::King
+            || !(self.restricted_corners && is_corner(x, y, size)
+                || self.restricted_throne && is_throne(x, y, size)
+                || self.is_citadel(x, y, size))
+    }
+
+    /// Whether `piece` may move over the empty square (x, y)
+    pub fn may_pass(&self, piece: Piece, x: usize, y: usize, size: usize) -> bool {
+        piece == Piece::King || self.throne_passable || !is_throne(x, y, size)
+    }
+
+    /// Whether `piece`, moving from `from`, may move onto or over the square (x, y) with regard
+    /// to camps
+    pub fn may_use_camp(
+        &self,
+        piece: Piece,
+        from: (usize, usize),
+        x: usize,
+        y: usize,
+        size: usize,
+    ) -> bool {
+        let is_camp = |x, y| {
+            self.camps
+                .contains(&Position::from_sized_indices(x, y, size))
+        };
+        !is_camp(x, y) || piece.color() == Player::Black && is_camp(from.0, from.1)
+    }
+
+    /// Whether the square (x, y) can replace a capturing piece when taking an ordinary piece,
+    /// see `Board::is_hostile_to` for the occupied throne
+    pub fn is_hostile(&self, x: usize, y: usize, size: usize) -> bool {
+        self.hostile_corners && is_corner(x, y, size)
+            || self.hostile_throne && is_throne(x, y, size)
+            || self.is_citadel(x, y, size)
+    }
+
+    /// Whether the empty square (x, y) counts as a hostile neighbour of the king, citadels
+    /// don't
+    pub fn is_hostile_to_king(&self, x: usize, y: usize, size: usize) -> bool {
+        self.hostile_corners && is_corner(x, y, size)
+            || self.hostile_throne && is_throne(x, y, size)
+    }
+
+    pub fn is_citadel(&self, x: usize, y: usize, size: usize) -> bool {
+        self.citadels
+            .contains(&Position::from_sized_indices(x, y, size))
+    }
+
+    pub fn is_escape(&self, x: usize, y: usize, size: usize) -> bool {
+        match &self.escape {
+            Escape::Corners => is_corner(x, y, size),
+            Escape::Edge => is_edge(x, y, size),
+            Escape::Squares(squares) => squares.contains(&Position::from_sized_indices(x, y, size)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::moves::{check_move, possible_moves, IllegalMove, Move, MoveOutcome};
+    use crate::types::{Board, Hnfen};
+
+    #[test]
+    fn default_is_copenhagen() {
+        let board = Board::default();
+        assert_eq!(board.rules, Ruleset::copenhagen());
+        assert_eq!(possible_moves(&board).len(), 116);
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(Ruleset::default().name(), Some("copenhagen"));
+        assert_eq!(Ruleset::from_name("fetlar"), Some(Ruleset::fetlar()));
+        assert_eq!(Ruleset::from_name("Fetlar"), None);
+        let custom = Ruleset {
+            hostile_edge: true,
+            ..Ruleset::default()
+        };
+        assert_eq!(custom.name(), None);
+    }
+
+    #[test]
+    fn first_player() {
+        let rules = Ruleset {
+            first: Player::White,
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with("11/11/11/11/11/11/11/11/11/11/11", rules).unwrap();
+        assert_eq!(board.next, Player::White);
+        let board =
+            Board::from_hnfen_with("11/11/11/11/11/11/11/11/11/11/11 a", Ruleset::default())
+                .unwrap();
+        assert_eq!(board.next, Player::Black);
+    }
+
+    #[test]
+    fn king_hostility() {
+        let corners_hostile = |hostile_corners| {
+            let rules = Ruleset {
+                king_capture_sides: 2,
+                hostile_corners,
+                escape: Escape::Squares(vec![Position::from_hnfen("f11").unwrap()]),
+                ..Ruleset::default()
+            };
+            let mut board =
+                Board::from_hnfen_with("11/11/11/11/11/11/11/2a8/11/11/1K9 a", rules).unwrap();
+            let mov = Move::from_hnfen("c4c1").unwrap();
+            let mut bits = crate::bitboard::BitBoard::from_board(&board).unwrap();
+            let king_captured = board.try_apply(&mov).unwrap().king_captured;
+            assert_eq!(bits.apply(&mov) != 0, king_captured);
+            king_captured
+        };
+        assert!(corners_hostile(true));
+        assert!(!corners_hostile(false));
+    }
+
+    #[test]
+    fn throne_passing() {
+        let hnfen = "11/11/11/11/11/a10/11/11/11/11/11";
+        let board = Board::from_hnfen(hnfen).unwrap();
+        assert_eq!(possible_moves(&board).len(), 8 + 9);
+
+        let rules = Ruleset {
+            throne_passable: false,
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with(hnfen, rules).unwrap();
+        assert_eq!(possible_moves(&board).len(), 8 + 4);
+        let mov = Move::from_hnfen("a6k6").unwrap();
+        assert!(check_move(&board, &mov).is_err());
+
+        let rules = Ruleset {
+            restricted_throne: false,
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with(hnfen, rules).unwrap();
+        assert_eq!(possible_moves(&board).len(), 8 + 10);
+    }
+
+    #[test]
+    fn unarmed_king() {
+        let hnfen = "11/11/11/11/11/11/11/11/1h9/1a9/3K7 h";
+        let mut board = Board::from_hnfen(hnfen).unwrap();
+        assert_eq!(
+            board
+                .apply(&Move::from_hnfen("d1b1").unwrap())
+                .captured
+                .len(),
+            1
+        );
+
+        let rules = Ruleset {
+            king_armed: KingArmed::Unarmed,
+            ..Ruleset::default()
+        };
+        let mut board = Board::from_hnfen_with(hnfen, rules.clone()).unwrap();
+        assert!(board
+            .apply(&Move::from_hnfen("d1b1").unwrap())
+            .captured
+            .is_empty());
+
+        let hnfen = "11/11/11/11/11/11/11/11/1K9/1a9/3h7 h";
+        let mut board = Board::from_hnfen_with(hnfen, rules).unwrap();
+        assert!(board
+            .apply(&Move::from_hnfen("d1b1").unwrap())
+            .captured
+            .is_empty());
+    }
+
+    #[test]
+    fn hostile_squares() {
+        let peaceful = Ruleset {
+            hostile_corners: false,
+            hostile_throne: false,
+            ..Ruleset::default()
+        };
+
+        let hnfen = "1h1a7/11/11/11/11/11/11/11/11/11/11";
+        let mut board = Board::from_hnfen_with(hnfen, peaceful.clone()).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("d11c11").unwrap());
+        assert!(outcome.captured.is_empty());
+        let mut board = Board::from_hnfen(hnfen).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("d11c11").unwrap());
+        assert_eq!(
+            outcome.captured,
+            vec![(Position::from_indices(1, 0), Piece::Normal(Player::White))]
+        );
+
+        let hnfen = "11/11/11/11/11/6h3a/11/11/11/11/11";
+        let mut board = Board::from_hnfen_with(hnfen, peaceful).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("k6h6").unwrap());
+        assert!(outcome.captured.is_empty());
+        let mut board = Board::from_hnfen(hnfen).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("k6h6").unwrap());
+        assert_eq!(
+            outcome.captured,
+            vec![(Position::from_indices(6, 5), Piece::Normal(Player::White))]
+        );
+    }
+
+    #[test]
+    fn occupied_throne() {
+        let unarmed = Ruleset {
+            king_armed: KingArmed::Unarmed,
+            ..Ruleset::default()
+        };
+
+        // Hostile to attackers even with a king that doesn't capture
+        let hnfen = "11/11/11/11/11/5Ka3h/11/11/11/11/11 h";
+        let mut board = Board::from_hnfen_with(hnfen, unarmed.clone()).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("k6h6").unwrap());
+        assert_eq!(
+            outcome.captured,
+            vec![(Position::from_indices(6, 5), Piece::Normal(Player::Black))]
+        );
+
+        // but never to defenders
+        let hnfen = "11/11/11/11/11/5Kh3a/11/11/11/11/11";
+        let mut board = Board::from_hnfen_with(hnfen, unarmed).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("k6h6").unwrap());
+        assert!(outcome.captured.is_empty());
+    }
+
+    #[test]
+    fn king_capture_sides() {
+        let hnfen = "11/11/11/11/11/11/11/11/3a7/2aK7/11";
+        let rules = Ruleset {
+            king_capture_sides: 2,
+            ..Ruleset::default()
+        };
+        let mut board = Board::from_hnfen_with(hnfen, rules.clone()).unwrap();
+        board.apply(&Move::from_hnfen("c2c1").unwrap());
+        assert!(board.king().is_some());
+        let mut board =
+            Board::from_hnfen_with("11/11/11/11/11/11/11/11/2aK7/11/4a6", rules).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("e1e3").unwrap());
+        assert!(outcome.king_captured);
+
+        let hnfen = "11/11/11/11/11/11/11/3a7/2aK7/11/3a7";
+        let mut board = Board::from_hnfen(hnfen).unwrap();
+        assert!(
+            !board
+                .apply(&Move::from_hnfen("d1d2").unwrap())
+                .king_captured
+        );
+        let rules = Ruleset {
+            king_capture_sides: 3,
+            ..Ruleset::default()
+        };
+        let mut board = Board::from_hnfen_with(hnfen, rules).unwrap();
+        assert!(
+            board
+                .apply(&Move::from_hnfen("d1d2").unwrap())
+                .king_captured
+        );
+    }
+
+    #[test]
+    fn king_capture_near_throne() {
+        let rules = Ruleset {
+            king_capture_sides: 2,
+            king_capture_sides_near_throne: 4,
+            ..Ruleset::default()
+        };
+        let capture = |hnfen: &str, mov: &str| {
+            let mut board = Board::from_hnfen_with(hnfen, rules.clone()).unwrap();
+            board.apply(&Move::from_hnfen(mov).unwrap()).king_captured
+        };
+
+        // Away from the throne two attackers are enough
+        assert!(capture("11/11/11/11/11/11/11/5a5/5K5/11/5a5", "f1f2"));
+        // Next to the throne it takes three and the throne
+        assert!(!capture("11/11/11/11/11/11/5K5/11/5a5/11/11", "f3f4"));
+        assert!(capture("11/11/11/11/11/11/4aKa4/11/5a5/11/11", "f3f4"));
+        // On the throne all four
+        assert!(!capture("11/11/11/11/5a5/4aK5/11/11/11/11/5a5", "f1f5"));
+    }
+
+    #[test]
+    fn hostile_edge() {
+        let hnfen = "11/11/11/11/11/11/11/11/11/4a6/3aK4a1";
+        let mut board = Board::from_hnfen(hnfen).unwrap();
+        assert!(
+            !board
+                .apply(&Move::from_hnfen("j1f1").unwrap())
+                .king_captured
+        );
+        let rules = Ruleset {
+            hostile_edge: true,
+            ..Ruleset::default()
+        };
+        let mut board = Board::from_hnfen_with(hnfen, rules).unwrap();
+        assert!(
+            board
+                .apply(&Move::from_hnfen("j1f1").unwrap())
+                .king_captured
+        );
+    }
+
+    #[test]
+    fn king_hammer_and_anvil() {
+        let hammer = Ruleset {
+            king_armed: KingArmed::Hammer,
+            ..Ruleset::default()
+        };
+        let anvil = Ruleset {
+            king_armed: KingArmed::Anvil,
+            ..Ruleset::default()
+        };
+        // King moves to capture
+        let hnfen = "11/11/11/11/11/11/11/11/1h9/1a9/3K7 h";
+        let mut board = Board::from_hnfen_with(hnfen, hammer.clone()).unwrap();
+        assert_eq!(
+            board
+                .apply(&Move::from_hnfen("d1b1").unwrap())
+                .captured
+                .len(),
+            1
+        );
+        let mut board = Board::from_hnfen_with(hnfen, anvil.clone()).unwrap();
+        assert!(board
+            .apply(&Move::from_hnfen("d1b1").unwrap())
+            .captured
+            .is_empty());
+        // Defender captures against the king
+        let hnfen = "11/11/11/11/11/11/11/11/1K9/1a9/3h7 h";
+        let mut board = Board::from_hnfen_with(hnfen, hammer).unwrap();
+        assert!(board
+            .apply(&Move::from_hnfen("d1b1").unwrap())
+            .captured
+            .is_empty());
+        let mut board = Board::from_hnfen_with(hnfen, anvil).unwrap();
+        assert_eq!(
+            board
+                .apply(&Move::from_hnfen("d1b1").unwrap())
+                .captured
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn edge_escape() {
+        let rules = Ruleset {
+            escape: Escape::Edge,
+            ..Ruleset::default()
+        };
+        let mut board =
+            Board::from_hnfen_with("11/11/11/11/5K5/11/11/11/11/11/11 h", rules).unwrap();
+        assert!(!board.king_escaped());
+        let outcome = board.apply(&Move::from_hnfen("f7f11").unwrap());
+        assert!(outcome.king_escaped);
+        assert!(board.king_escaped());
+
+        // Corners don't count unless listed
+        let rules = Ruleset {
+            escape: Escape::Squares(vec![
+                Position::from_hnfen("a6").unwrap(),
+                Position::from_hnfen("f11").unwrap(),
+            ]),
+            ..Ruleset::default()
+        };
+        let mut board =
+            Board::from_hnfen_with("11/11/11/11/11/1K9/11/11/11/11/11 h", rules.clone()).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("b6a6").unwrap());
+        assert!(outcome.king_escaped);
+        assert_eq!(board.status().winner(), Some(Player::White));
+        let mut board =
+            Board::from_hnfen_with("11/11/11/11/11/1K9/11/11/11/11/11 h", rules).unwrap();
+        board.apply(&Move::from_hnfen("b6b11").unwrap());
+        board.next = Player::White;
+        let outcome = board.apply(&Move::from_hnfen("b11a11").unwrap());
+        assert!(!outcome.king_escaped);
+        assert!(!board.king_escaped());
+    }
+
+    fn play(hnfen: &str, mov: &str) -> (Board, MoveOutcome) {
+        let mut board = Board::from_hnfen_with(hnfen, Ruleset::copenhagen()).unwrap();
+        let outcome = board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+        (board, outcome)
+    }
+
+    fn taken(outcome: &MoveOutcome) -> Vec<String> {
+        let mut taken: Vec<String> = outcome
+            .captured
+            .iter()
+            .map(|(p, _)| p.to_string())
+            .collect();
+        taken.sort();
+        taken
+    }
+
+    #[test]
+    fn copenhagen_start() {
+        let board =
+            Board::from_hnfen_with(crate::DEFAULT_START_HNFEN, Ruleset::copenhagen()).unwrap();
+        assert_eq!(possible_moves(&board).len(), 116);
+        assert_eq!(board.status(), crate::status::GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn copenhagen_hostile_squares() {
+        // Corner is hostile to both sides
+        let (_, outcome) = play("1h1a7/11/11/11/11/11/11/11/11/11/11 a", "d11c11");
+        assert_eq!(taken(&outcome), vec!["b11"]);
+        let (_, outcome) = play("11/a10/11/h10/11/11/11/11/11/11/11 h", "a8a9");
+        assert_eq!(taken(&outcome), vec!["a10"]);
+
+        // Empty throne is hostile to defenders
+        let (_, outcome) = play("11/11/11/11/11/6h3a/11/11/11/11/11 a", "k6h6");
+        assert_eq!(taken(&outcome), vec!["g6"]);
+        // and to attackers
+        let (_, outcome) = play("11/11/11/11/11/6a3h/11/11/11/11/11 h", "k6h6");
+        assert_eq!(taken(&outcome), vec!["g6"]);
+        // The king on the throne is no threat to its own defenders
+        let (_, outcome) = play("11/11/11/11/11/5Kh3a/11/11/11/11/11 a", "k6h6");
+        assert!(outcome.captured.is_empty());
+        // but takes part in capturing attackers
+        let (_, outcome) = play("11/11/11/11/11/5Ka3h/11/11/11/11/11 h", "k6h6");
+        assert_eq!(taken(&outcome), vec!["g6"]);
+    }
+
+    #[test]
+    fn copenhagen_king_capture() {
+        // Four attackers around the throne
+        let (_, outcome) = play("11/11/11/11/5a5/4aK1a3/5a5/11/11/11/11 a", "h6g6");
+        assert!(outcome.king_captured);
+        let (_, outcome) = play("11/11/11/11/5a5/4aK1a3/11/11/11/11/11 a", "h6g6");
+        assert!(!outcome.king_captured);
+        // Three attackers and the throne
+        let (_, outcome) = play("11/11/11/5a5/4aK1a3/11/11/11/11/11/11 a", "h7g7");
+        assert!(outcome.king_captured);
+
+        // The empty throne is hostile to attackers, the occupied one takes them by the king
+        let (_, outcome) = play("11/11/11/11/11/4a6/11/11/11/11/3h7 h", "d1d6");
+        assert_eq!(taken(&outcome), vec!["e6"]);
+        let (_, outcome) = play("11/11/11/11/11/4aK5/11/11/11/11/3h7 h", "d1d6");
+        assert_eq!(taken(&outcome), vec!["e6"]);
+        // Not on the edge
+        let (board, outcome) = play("11/11/11/11/11/11/11/11/11/4a6/3aK1a4 a", "g1f1");
+        assert!(!outcome.king_captured);
+        assert!(board.king().is_some());
+    }
+
+    #[test]
+    fn copenhagen_shieldwall() {
+        // Bracketed by attackers on both ends
+        let (_, outcome) = play("11/11/11/11/11/11/11/11/11/3aa6/2ahh3a2 a", "i1f1");
+        assert_eq!(taken(&outcome), vec!["d1", "e1"]);
+        // The corner counts as a bracket
+        let (_, outcome) = play("11/11/11/11/11/11/11/11/11/1aa8/1hh4a3 a", "h1d1");
+        assert_eq!(taken(&outcome), vec!["b1", "c1"]);
+        // Every piece in the row has to be blocked from the front
+        let (_, outcome) = play("11/11/11/11/11/11/11/11/11/3a7/2ahh3a2 a", "i1f1");
+        assert!(outcome.captured.is_empty());
+        // The king is part of the wall, but stays
+        let (board, outcome) = play("11/11/11/11/11/11/11/11/11/3aaa5/2ahKh2a2 a", "i1g1");
+        assert_eq!(taken(&outcome), vec!["d1", "f1"]);
+        assert!(board.king().is_some());
+        // Defenders capture attackers the same way, here with the king as a bracket
+        let (_, outcome) = play("11/11/K10/ah9/ah9/11/11/11/h10/11/11 h", "a3a6");
+        assert_eq!(taken(&outcome), vec!["a7", "a8"]);
+
+        let rules = Ruleset {
+            shieldwall: false,
+            ..Ruleset::copenhagen()
+        };
+        let mut board =
+            Board::from_hnfen_with("11/11/11/11/11/11/11/11/11/3aa6/2ahh3a2 a", rules).unwrap();
+        let outcome = board.apply(&Move::from_hnfen("i1f1").unwrap());
+        assert!(outcome.captured.is_empty());
+    }
+
+    #[test]
+    fn shieldwall_edges() {
+        let wall = |hnfen: &str, mov: &str| {
+            let mut board = Board::from_hnfen(hnfen).unwrap();
+            let outcome = board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+            taken(&outcome)
+        };
+
+        // Top edge, three pieces between attackers and against the corner
+        assert_eq!(
+            wall("1ahhh1a4/2aaa6/11/11/11/11/11/11/11/11/11 a", "g11f11"),
+            vec!["c11", "d11", "e11"]
+        );
+        assert_eq!(
+            wall("1hhh1a5/1aaa7/11/11/11/11/11/11/11/11/11 a", "f11e11"),
+            vec!["b11", "c11", "d11"]
+        );
+        // Right edge, between two attackers
+        assert_eq!(
+            wall("11/11/10a/9ah/9ah/11/10a/11/11/11/11 a", "k5k6"),
+            vec!["k7", "k8"]
+        );
+        // A single piece is only taken the ordinary way
+        assert_eq!(
+            wall("11/11/10a/9ah/11/11/10a/11/11/11/11 a", "k5k7"),
+            vec!["k8"]
+        );
+        assert!(wall("11/11/10a/9a1/9ah/11/10a/11/11/11/11 a", "k5k6").is_empty());
+        // The row ends at the first gap
+        assert!(wall("11/11/10a/9ah/9ah/11/11/11/10a/11/11 a", "k3k5").is_empty());
+    }
+
+    #[test]
+    fn fetlar() {
+        let rules = Ruleset::fetlar();
+        let play = |hnfen: &str, mov: &str| {
+            let mut board = Board::from_hnfen_with(hnfen, rules.clone()).unwrap();
+            let outcome = board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+            (board, outcome)
+        };
+
+        // Captures against corners and throne
+        let (_, outcome) = play("1h1a7/11/11/11/11/11/11/11/11/11/11 a", "d11c11");
+        assert_eq!(taken(&outcome), vec!["b11"]);
+        let (_, outcome) = play("11/11/11/11/11/6h3a/11/11/11/11/11 a", "k6h6");
+        assert_eq!(taken(&outcome), vec!["g6"]);
+        let (_, outcome) = play("11/11/11/11/11/5Kh3a/11/11/11/11/11 a", "k6h6");
+        assert!(outcome.captured.is_empty());
+        let (_, outcome) = play("11/11/11/5a5/4aK1a3/11/11/11/11/11/11 a", "h7g7");
+        assert!(outcome.king_captured);
+
+        // The empty throne is hostile to attackers, the occupied one takes them by the king
+        let (_, outcome) = play("11/11/11/11/11/4a6/11/11/11/11/3h7 h", "d1d6");
+        assert_eq!(taken(&outcome), vec!["e6"]);
+        let (_, outcome) = play("11/11/11/11/11/4aK5/11/11/11/11/3h7 h", "d1d6");
+        assert_eq!(taken(&outcome), vec!["e6"]);
+
+        // No shieldwall, only the single piece next to the moved one is taken
+        let (_, outcome) = play("11/11/11/11/11/11/11/11/11/3aa6/2ahh3a2 a", "i1f1");
+        assert!(outcome.captured.is_empty());
+        let (_, outcome) = play("11/11/11/11/11/11/11/11/11/1aa8/1hh4a3 a", "h1d1");
+        assert!(outcome.captured.is_empty());
+
+        // Neither forts nor rings end the game
+        let fort = "11/11/11/a10/11/11/11/11/4hhh4/4h1h4/4hKh4 a";
+        let board = Board::from_hnfen_with(fort, rules.clone()).unwrap();
+        assert!(board.is_exit_fort());
+        assert_eq!(board.status(), crate::status::GameStatus::Ongoing);
+        let ring = "11/11/11/4aaa4/3a3a3/3a1K1a3/3a1h1a3/4aaa4/11/11/11 h";
+        let board = Board::from_hnfen_with(ring, rules.clone()).unwrap();
+        assert!(board.is_encircled());
+        assert_eq!(board.status(), crate::status::GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn brandubh() {
+        let board = Board::from_hnfen(crate::BRANDUBH_START_HNFEN).unwrap();
+        assert_eq!(board.rules, Ruleset::brandubh());
+        assert_eq!(board.size(), 7);
+        assert_eq!(board.as_hnfen(), crate::BRANDUBH_START_HNFEN);
+        assert_eq!(possible_moves(&board).len(), 40);
+        assert_eq!(board.status(), crate::status::GameStatus::Ongoing);
+
+        let play = |hnfen: &str, mov: &str| {
+            let mut board = Board::from_hnfen_with(hnfen, Ruleset::brandubh()).unwrap();
+            let outcome = board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+            (board, outcome)
+        };
+        // Two attackers take the king away from the throne, but not on it
+        let (_, outcome) = play("7/1aK3a/7/7/7/7/7 a", "g6d6");
+        assert!(outcome.king_captured);
+        let (_, outcome) = play("7/7/7/2aK2a/7/7/7 a", "g4e4");
+        assert!(!outcome.king_captured);
+        // The pair has to include the attacker that moves
+        let (_, outcome) = play("7/7/7/7/1aKa3/6a/7 a", "g2c2");
+        assert!(!outcome.king_captured);
+        let rules = Ruleset {
+            king_capture_sides: 2,
+            ..Ruleset::default()
+        };
+        let mut board =
+            Board::from_hnfen_with("11/11/11/11/11/11/11/10a/4aKa4/11/11 a", rules).unwrap();
+        let outcome = board.try_apply(&Move::from_hnfen("k4f4").unwrap()).unwrap();
+        assert!(!outcome.king_captured);
+        // Corners and the empty throne are hostile
+        let (_, outcome) = play("7/7/7/7/7/2a4/1h5 a", "c2c1");
+        assert_eq!(taken(&outcome), vec!["b1"]);
+        let (_, outcome) = play("7/2a4/3h3/7/7/7/7 a", "c6d6");
+        assert_eq!(taken(&outcome), vec!["d5"]);
+        let (board, outcome) = play("7/7/7/7/7/7/1K3a1 h", "b1a1");
+        assert!(outcome.king_escaped);
+        assert_eq!(board.status().winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn tablut() {
+        let board = Board::from_hnfen(crate::TABLUT_START_HNFEN).unwrap();
+        assert_eq!(board.rules, Ruleset::tablut());
+        assert_eq!(board.size(), 9);
+        assert_eq!(board.as_hnfen(), crate::TABLUT_START_HNFEN);
+        assert_eq!(possible_moves(&board).len(), 80);
+
+        // Only attackers starting on a camp may move onto or over camps
+        let check = |hnfen: &str, mov: &str| {
+            let board = Board::from_hnfen_with(hnfen, Ruleset::tablut()).unwrap();
+            check_move(&board, &Move::from_hnfen(mov).unwrap())
+        };
+        let restricted = Err(IllegalMove::RestrictedSquare);
+        assert_eq!(check("9/9/9/9/2h6/9/9/9/9 h", "c5b5"), restricted);
+        assert_eq!(check("9/9/9/9/2a6/9/9/9/9 a", "c5b5"), restricted);
+        assert_eq!(check("9/9/9/9/9/9/9/2K6/9 h", "c2g2"), restricted);
+        assert_eq!(check("9/9/9/9/a8/9/9/9/9 a", "a5a4"), Ok(()));
+        assert_eq!(check("9/9/9/9/a8/9/9/9/9 a", "a5a2"), Ok(()));
+        let board = Board::from_hnfen_with("9/9/9/9/9/9/9/2K6/9 h", Ruleset::tablut()).unwrap();
+        assert!(!possible_moves(&board)
+            .iter()
+            .any(|mov| mov.to == Position::from_hnfen("f2").unwrap()));
+
+        let play = |hnfen: &str, mov: &str| {
+            let mut board = Board::from_hnfen_with(hnfen, Ruleset::tablut()).unwrap();
+            let outcome = board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+            (board, outcome)
+        };
+        // Two attackers take the king away from the throne, next to it three and the throne
+        let (_, outcome) = play("9/9/9/9/9/9/1aK2a3/9/9 a", "f3d3");
+        assert!(outcome.king_captured);
+        let (_, outcome) = play("9/9/9/3aK2a1/9/9/9/9/9 a", "h6f6");
+        assert!(!outcome.king_captured);
+        let (_, outcome) = play("9/9/4a4/3aK2a1/9/9/9/9/9 a", "h6f6");
+        assert!(outcome.king_captured);
+        let (board, outcome) = play("9/9/9/9/9/6a2/9/1K7/9 h", "b2b1");
+        assert!(outcome.king_escaped);
+        assert_eq!(board.status().winner(), Some(Player::White));
+    }
+
+    #[test]
+    fn large_boards() {
+        let board = Board::from_hnfen(crate::TAWLBWRDD_START_HNFEN).unwrap();
+        assert_eq!(board.rules, Ruleset::tawlbwrdd());
+        assert_eq!(board.size(), 11);
+        assert_eq!(board.as_hnfen(), crate::TAWLBWRDD_START_HNFEN);
+        assert_eq!(board.pieces(Player::Black).len(), 24);
+        assert_eq!(board.pieces(Player::White).len(), 13);
+
+        let board = Board::from_hnfen(crate::ALEA_EVANGELII_START_HNFEN).unwrap();
+        assert_eq!(board.rules, Ruleset::alea_evangelii());
+        assert_eq!(board.size(), 19);
+        assert_eq!(board.as_hnfen(), crate::ALEA_EVANGELII_START_HNFEN);
+        assert_eq!(board.pieces(Player::Black).len(), 48);
+        assert_eq!(board.pieces(Player::White).len(), 25);
+        assert_eq!(board.status(), crate::status::GameStatus::Ongoing);
+
+        let play = |hnfen: &str, mov: &str, rules: Ruleset| {
+            let mut board = Board::from_hnfen_with(hnfen, rules).unwrap();
+            let outcome = board.try_apply(&Move::from_hnfen(mov).unwrap()).unwrap();
+            (board, outcome)
+        };
+        // Tawlbwrdd: any edge square wins, corners are neither restricted nor hostile
+        let (_, outcome) = play(
+            "11/11/11/11/11/11/11/11/3K7/11/9a1 h",
+            "d3d1",
+            Ruleset::tawlbwrdd(),
+        );
+        assert!(outcome.king_escaped);
+        let (_, outcome) = play(
+            "11/11/11/11/11/11/11/11/11/a10/1h7a1 a",
+            "j1c1",
+            Ruleset::tawlbwrdd(),
+        );
+        assert!(outcome.captured.is_empty());
+        let (_, outcome) = play(
+            "11/11/11/11/11/11/11/11/11/a10/1h7a1 a",
+            "a2a1",
+            Ruleset::tawlbwrdd(),
+        );
+        assert!(outcome.captured.is_empty());
+        // Alea Evangelii: corners on 19x19
+        let board = Board::from_hnfen_with(
+            "19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/a17K a",
+            Ruleset::alea_evangelii(),
+        )
+        .unwrap();
+        assert_eq!(
+            check_move(&board, &Move::from_hnfen("a1a19").unwrap()),
+            Err(IllegalMove::RestrictedSquare)
+        );
+        assert!(board.status().is_over());
+
+        // Alea Evangelii: only the king stops on citadels, empty ones take part in captures
+        let board = Board::from_hnfen_with(
+            "19/19/19/19/19/19/19/19/19/19/19/19/19/19/6h12/19/19/19/19 h",
+            Ruleset::alea_evangelii(),
+        )
+        .unwrap();
+        assert_eq!(
+            check_move(&board, &Move::from_hnfen("g5g7").unwrap()),
+            Err(IllegalMove::RestrictedSquare)
+        );
+        let targets: Vec<String> = possible_moves(&board)
+            .iter()
+            .map(|mov| mov.to.to_string())
+            .collect();
+        assert!(!targets.contains(&"g7".to_string()));
+        assert!(targets.contains(&"g8".to_string()));
+        let (_, outcome) = play(
+            "19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/3a15/19/4h14 h",
+            "e1e3",
+            Ruleset::alea_evangelii(),
+        );
+        assert_eq!(taken(&outcome), vec!["d3"]);
+        // Citadels aren't hostile to the king
+        let (_, outcome) = play(
+            "19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/19/3K1a13/19/19 a",
+            "f3e3",
+            Ruleset::alea_evangelii(),
+        );
+        assert!(!outcome.king_captured);
+    }
+}
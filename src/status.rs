@@ -0,0 +1,424 @@
+use crate::moves::{in_board, is_edge, possible_moves, Direction, Position};
+use crate::types::{Board, Player};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameStatus {
+    Ongoing,
+    Won {
+        winner: Player,
+        reason: WinReason,
+    },
+    /// Only reached through repetition, see `History::status`
+    Drawn,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum WinReason {
+    /// King reached an escape square, white wins
+    KingEscaped,
+    /// King is no longer on the board, black wins
+    KingCaptured,
+    /// King is on the edge in a fort black can't break, white wins
+    ExitFort,
+    /// All white pieces are enclosed by black, black wins
+    Encircled,
+    /// The side to move has no legal moves left and loses
+    NoMoves,
+    /// A position occurred too often, see `Repetition`
+    Repetition,
+}
+
+impl GameStatus {
+    pub fn winner(&self) -> Option<Player> {
+        match self {
+            GameStatus::Ongoing | GameStatus::Drawn => None,
+            GameStatus::Won { winner, .. } => Some(*winner),
+        }
+    }
+
+    pub fn is_over(&self) -> bool {
+        *self != GameStatus::Ongoing
+    }
+}
+
+impl Board {
+    pub fn status(&self) -> GameStatus {
+        let (winner, reason) = if self.king_escaped() {
+            (Player::White, WinReason::KingEscaped)
+        } else if self.king().is_none() {
+            (Player::Black, WinReason::KingCaptured)
+        } else if self.rules.exit_forts && self.is_exit_fort() {
+            (Player::White, WinReason::ExitFort)
+        } else if self.rules.encirclement && self.is_encircled() {
+            (Player::Black, WinReason::Encircled)
+        } else if possible_moves(self).is_empty() {
+            (self.next.opposite(), WinReason::NoMoves)
+        } else {
+            return GameStatus::Ongoing;
+        };
+        GameStatus::Won { winner, reason }
+    }
+
+    /// Whether the king touches the edge, can move, and is walled in by white pieces that can't
+    /// be captured
+    pub fn is_exit_fort(&self) -> bool {
+        self.exit_fort().is_some()
+    }
+
+    /// The white pieces walling in the king if it sits in an exit fort, see `is_exit_fort`
+    pub fn exit_fort(&self) -> Option<Vec<Position>> {
+        let size = self.size();
+        let king = self.king()?;
+        let (king_x, king_y) = king.to_sized_indices(size);
+        if !is_edge(king_x, king_y, size) {
+            return None;
+        }
+
+        // The king and the empty squares it can reach
+        let inside = self.region(&[king], |p| self.get(p).is_none());
+        if inside.iter().flatten().filter(|i| **i).count() < 2 {
+            // King can't move
+            return None;
+        }
+
+        // White pieces that keep each other safe. Pieces are dropped until every remaining one
+        // has a side on each axis that black can never occupy.
+        let mut safe = vec![vec![false; size]; size];
+        for pos in self.pieces(Player::White) {
+            let (x, y) = pos.to_sized_indices(size);
+            safe[y][x] = !inside[y][x];
+        }
+        let protects = |safe: &[Vec<bool>], x: isize, y: isize| {
+            if !in_board(x, y, size) {
+                return true;
+            }
+            let (x, y) = (x as usize, y as usize);
+            safe[y][x]
+                || inside[y][x]
+                    && !self.is_hostile_to(&Position::from_sized_indices(x, y, size), Player::White)
+        };
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for y in 0..size {
+                for x in 0..size {
+                    if !safe[y][x] {
+                        continue;
+                    }
+                    let axes = [
+                        [Direction::Up, Direction::Down],
+                        [Direction::Left, Direction::Right],
+                    ];
+                    let capturable = axes.iter().any(|axis| {
+                        axis.iter().all(|dir| {
+                            let (dx, dy) = dir.vector(1);
+                            !protects(&safe, x as isize + dx, y as isize + dy)
+                        })
+                    });
+                    if capturable {
+                        safe[y][x] = false;
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        // Everything bordering the inside has to be a safe white piece
+        let mut wall = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                if !inside[y][x] {
+                    continue;
+                }
+                for dir in Direction::card().iter() {
+                    let (dx, dy) = dir.vector(1);
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    if !in_board(nx, ny, size) {
+                        continue;
+                    }
+                    let (nx, ny) = (nx as usize, ny as usize);
+                    if inside[ny][nx] {
+                        continue;
+                    }
+                    if !safe[ny][nx] {
+                        return None;
+                    }
+                    let pos = Position::from_sized_indices(nx, ny, size);
+                    if !wall.contains(&pos) {
+                        wall.push(pos);
+                    }
+                }
+            }
+        }
+        Some(wall)
+    }
+
+    /// Whether black encloses every white piece, so that none of them can reach the edge
+    pub fn is_encircled(&self) -> bool {
+        self.encirclement().is_some()
+    }
+
+    /// The black pieces forming the ring if white is encircled, see `is_encircled`
+    pub fn encirclement(&self) -> Option<Vec<Position>> {
+        let size = self.size();
+        let white = self.pieces(Player::White);
+        if white.is_empty() {
+            return None;
+        }
+        let is_black = |p: &Position| self.get(p).is_some_and(|p| p.color() == Player::Black);
+        let reachable = self.region(&white, |p| !is_black(p));
+
+        // Black pieces next to what white can reach, some of them may be inside the ring
+        let mut border = vec![vec![false; size]; size];
+        for (y, rank) in reachable.iter().enumerate() {
+            for (x, reached) in rank.iter().enumerate() {
+                if !reached {
+                    continue;
+                }
+                if is_edge(x, y, size) {
+                    return None;
+                }
+                for dir in Direction::card().iter() {
+                    let (dx, dy) = dir.vector(1);
+                    // Not on the edge, so every neighbour is on the board
+                    let (nx, ny) = ((x as isize + dx) as usize, (y as isize + dy) as usize);
+                    border[ny][nx] = !reachable[ny][nx];
+                }
+            }
+        }
+
+        // Everything outside of the ring is connected to the edge
+        let open = |p: &Position| {
+            let (x, y) = p.to_sized_indices(size);
+            !reachable[y][x] && !border[y][x]
+        };
+        let edge: Vec<Position> = (0..size)
+            .flat_map(|k| vec![(k, 0), (k, size - 1), (0, k), (size - 1, k)])
+            .map(|(x, y)| Position::from_sized_indices(x, y, size))
+            .filter(|p| open(p))
+            .collect();
+        let outside = self.region(&edge, open);
+
+        let mut ring = Vec::new();
+        for (y, rank) in border.iter().enumerate() {
+            for (x, on_border) in rank.iter().enumerate() {
+                let touches_outside = Direction::card().iter().any(|dir| {
+                    let (dx, dy) = dir.vector(1);
+                    let (nx, ny) = (x as isize + dx, y as isize + dy);
+                    !in_board(nx, ny, size) || outside[ny as usize][nx as usize]
+                });
+                if *on_border && touches_outside {
+                    ring.push(Position::from_sized_indices(x, y, size));
+                }
+            }
+        }
+        Some(ring)
+    }
+
+    /// Squares reachable from `starts` through orthogonal steps onto squares `pass` accepts,
+    /// indexed like `ranks`
+    fn region(&self, starts: &[Position], pass: impl Fn(&Position) -> bool) -> Vec<Vec<bool>> {
+        let size = self.size();
+        let mut reached = vec![vec![false; size]; size];
+        let mut todo: Vec<(usize, usize)> =
+            starts.iter().map(|p| p.to_sized_indices(size)).collect();
+        for (x, y) in todo.iter() {
+            reached[*y][*x] = true;
+        }
+        while let Some((x, y)) = todo.pop() {
+            for dir in Direction::card().iter() {
+                let (dx, dy) = dir.vector(1);
+                let (nx, ny) = (x as isize + dx, y as isize + dy);
+                if !in_board(nx, ny, size) {
+                    continue;
+                }
+                let (nx, ny) = (nx as usize, ny as usize);
+                if !reached[ny][nx] && pass(&Position::from_sized_indices(nx, ny, size)) {
+                    reached[ny][nx] = true;
+                    todo.push((nx, ny));
+                }
+            }
+        }
+        reached
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::rules::Ruleset;
+    use crate::types::Hnfen;
+
+    #[test]
+    fn default_is_ongoing() {
+        let status = Board::default().status();
+        assert_eq!(status, GameStatus::Ongoing);
+        assert!(!status.is_over());
+        assert_eq!(status.winner(), None);
+    }
+
+    #[test]
+    fn king_escaped() {
+        let board = Board::from_hnfen("K10/11/11/11/11/11/11/11/11/11/10a").unwrap();
+        assert_eq!(
+            board.status(),
+            GameStatus::Won {
+                winner: Player::White,
+                reason: WinReason::KingEscaped
+            }
+        );
+    }
+
+    #[test]
+    fn king_captured() {
+        let board = Board::from_hnfen("11/11/11/11/11/5a5/11/11/11/11/11 h").unwrap();
+        assert_eq!(
+            board.status(),
+            GameStatus::Won {
+                winner: Player::Black,
+                reason: WinReason::KingCaptured
+            }
+        );
+    }
+
+    #[test]
+    fn exit_fort() {
+        let rules = Ruleset::copenhagen();
+        let fort = "11/11/11/a10/11/11/11/11/4hhh4/4h1h4/4hKh4 a";
+        let board = Board::from_hnfen_with(fort, rules.clone()).unwrap();
+        assert!(board.is_exit_fort());
+        assert_eq!(
+            board.status(),
+            GameStatus::Won {
+                winner: Player::White,
+                reason: WinReason::ExitFort
+            }
+        );
+        let mut wall: Vec<String> = board
+            .exit_fort()
+            .unwrap()
+            .iter()
+            .map(Position::to_string)
+            .collect();
+        wall.sort();
+        assert_eq!(wall, vec!["e1", "e2", "f3", "g1", "g2"]);
+        // Same fort without the rule
+        let without = Ruleset {
+            exit_forts: false,
+            ..Ruleset::copenhagen()
+        };
+        let board = Board::from_hnfen_with(fort, without).unwrap();
+        assert_eq!(board.status(), GameStatus::Ongoing);
+        // Part of the default rules
+        let board = Board::from_hnfen(fort).unwrap();
+        assert_eq!(board.status().winner(), Some(Player::White));
+
+        // Open to the top
+        let board = Board::from_hnfen_with(
+            "11/11/11/a10/11/11/11/11/4h1h4/4h1h4/4hKh4 a",
+            rules.clone(),
+        )
+        .unwrap();
+        assert!(!board.is_exit_fort());
+        // Top of the fort can be taken from the sides
+        let board =
+            Board::from_hnfen_with("11/11/11/a10/11/11/11/11/5h5/4h1h4/4hKh4 a", rules.clone())
+                .unwrap();
+        assert!(!board.is_exit_fort());
+        // Black is inside
+        let board =
+            Board::from_hnfen_with("11/11/11/11/11/11/11/11/4hhh4/4hah4/4hKh4 a", rules.clone())
+                .unwrap();
+        assert!(!board.is_exit_fort());
+        // King can't move
+        let board =
+            Board::from_hnfen_with("11/11/11/a10/11/11/11/11/11/5h5/4hKh4 a", rules.clone())
+                .unwrap();
+        assert!(!board.is_exit_fort());
+        // Not on the edge
+        let board =
+            Board::from_hnfen_with("11/11/11/a10/11/11/11/4hhh4/4h1h4/4hKh4/4hhh4 a", rules)
+                .unwrap();
+        assert!(!board.is_exit_fort());
+    }
+
+    #[test]
+    fn encircled() {
+        let rules = Ruleset::copenhagen();
+        let ring = "11/11/11/4aaa4/3a3a3/3a1K1a3/3a1h1a3/4aaa4/11/11/11 h";
+        let board = Board::from_hnfen_with(ring, rules.clone()).unwrap();
+        assert!(board.is_encircled());
+        assert_eq!(
+            board.status(),
+            GameStatus::Won {
+                winner: Player::Black,
+                reason: WinReason::Encircled
+            }
+        );
+        let mut pieces: Vec<String> = board
+            .encirclement()
+            .unwrap()
+            .iter()
+            .map(Position::to_string)
+            .collect();
+        pieces.sort();
+        assert_eq!(
+            pieces,
+            vec!["d5", "d6", "d7", "e4", "e8", "f4", "f8", "g4", "g8", "h5", "h6", "h7"]
+        );
+        let without = Ruleset {
+            encirclement: false,
+            ..Ruleset::copenhagen()
+        };
+        let board = Board::from_hnfen_with(ring, without).unwrap();
+        assert_eq!(board.status(), GameStatus::Ongoing);
+        let board = Board::from_hnfen(ring).unwrap();
+        assert_eq!(board.status().winner(), Some(Player::Black));
+
+        // Black pieces inside the ring don't belong to it
+        let board = Board::from_hnfen_with(
+            "11/11/11/4aaa4/3a3a3/3aaK1a3/3a1h1a3/4aaa4/11/11/11 h",
+            rules.clone(),
+        )
+        .unwrap();
+        assert_eq!(board.encirclement().unwrap().len(), 12);
+        // A thick ring reports its inner layer
+        let board = Board::from_hnfen_with(
+            "11/11/4aaa4/3aaaaa3/3a3a3/3a1K1a3/3a1h1a3/3aaaaa3/4aaa4/11/11 h",
+            rules.clone(),
+        )
+        .unwrap();
+        assert_eq!(board.encirclement().unwrap().len(), 12);
+
+        // Gap in the ring
+        let board = Board::from_hnfen_with(
+            "11/11/11/4aaa4/3a3a3/3a1K1a3/3a1h5/4aaa4/11/11/11 h",
+            rules.clone(),
+        )
+        .unwrap();
+        assert!(!board.is_encircled());
+        // A defender outside
+        let board = Board::from_hnfen_with(
+            "11/11/11/4aaa4/3a3a3/3a1K1a3/3a1h1a3/4aaa4/11/11/h10 h",
+            rules,
+        )
+        .unwrap();
+        assert!(!board.is_encircled());
+    }
+
+    #[test]
+    fn no_moves() {
+        // King is boxed in against the edge
+        let board = Board::from_hnfen("1aKa7/2a8/11/11/11/11/11/11/11/11/11 h").unwrap();
+        assert_eq!(possible_moves(&board).len(), 0);
+        assert_eq!(
+            board.status(),
+            GameStatus::Won {
+                winner: Player::Black,
+                reason: WinReason::NoMoves
+            }
+        );
+    }
+}
@@ -1,28 +1,128 @@
-use std::convert::TryInto;
-
-use crate::moves::{in_board, is_castle, is_corner, Direction, Move, Position};
+use crate::error::{ParseError, ParseErrorKind};
+use crate::moves::{
+    check_move, in_board, is_throne, Direction, IllegalMove, Move, MoveOutcome, Position,
+};
+use crate::rules::Ruleset;
+use crate::zobrist;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Compared, hashed and serialized without its Zobrist key, which is recomputed when
+/// deserializing
+#[derive(Debug, Clone, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BoardFields")]
 pub struct Board {
-    pub ranks: [Rank; 11],
+    /// Ranks from the top down, as many as each rank has fields
+    pub ranks: Vec<Rank>,
     pub next: Player,
+    #[serde(default)]
+    pub rules: Ruleset,
+    /// Plies played before this position
+    #[serde(default)]
+    pub ply: u32,
+    /// Plies played since the last capture
+    #[serde(default)]
+    pub since_capture: u32,
+    /// Zobrist key of the pieces, kept up to date by `set` and `apply`, see `zobrist`
+    #[serde(skip)]
+    pub(crate) zobrist_pieces: u64,
+}
+
+/// Serialized fields of `Board`
+#[derive(Deserialize)]
+struct BoardFields {
+    ranks: Vec<Rank>,
+    next: Player,
+    #[serde(default)]
+    rules: Ruleset,
+    #[serde(default)]
+    ply: u32,
+    #[serde(default)]
+    since_capture: u32,
+}
+
+impl std::convert::TryFrom<BoardFields> for Board {
+    type Error = ParseError;
+
+    /// Checks the ranks like `Board::from_hnfen_with`, with errors at offset 0
+    fn try_from(fields: BoardFields) -> Result<Self, Self::Error> {
+        let size = fields.ranks.len();
+        if size.is_multiple_of(2) || !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+            return Err(ParseError::new(0, ParseErrorKind::WrongRankCount(size)));
+        }
+        for rank in &fields.ranks {
+            match rank.fields.len() {
+                len if len < size => {
+                    return Err(ParseError::new(0, ParseErrorKind::RankUnderflow(len)))
+                }
+                len if len > size => return Err(ParseError::new(0, ParseErrorKind::RankOverflow)),
+                _ => (),
+            }
+        }
+        let mut board = Board {
+            ranks: fields.ranks,
+            next: fields.next,
+            rules: fields.rules,
+            ply: fields.ply,
+            since_capture: fields.since_capture,
+            zobrist_pieces: 0,
+        };
+        board.refresh_zobrist();
+        Ok(board)
+    }
+}
+
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.ranks == other.ranks
+            && self.next == other.next
+            && self.rules == other.rules
+            && self.ply == other.ply
+            && self.since_capture == other.since_capture
+    }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+impl std::hash::Hash for Board {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.ranks.hash(state);
+        self.next.hash(state);
+        self.rules.hash(state);
+        self.ply.hash(state);
+        self.since_capture.hash(state);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
 pub struct Rank {
-    pub fields: [Option<Piece>; 11],
+    pub fields: Vec<Option<Piece>>,
 }
 
 const WHITE: &str = "h";
 const BLACK: &str = "a";
 const KING: &str = "K";
+const BLACK_COMMANDER: &str = "c";
+const WHITE_COMMANDER: &str = "C";
+const BLACK_KNIGHT: &str = "n";
+const WHITE_KNIGHT: &str = "N";
 const RANK_SEP: &str = "/";
+/// Ruleset field of extended hnfen for rules without a name
+const UNNAMED_RULES: &str = "-";
+/// Size of boards whose hnfen doesn't need the dimensions
+const DEFAULT_SIZE: usize = 11;
+/// Largest number of ranks and files a board may have
+pub const MAX_SIZE: usize = 19;
+const MIN_SIZE: usize = 5;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Piece {
     Normal(Player),
+    /// The defenders' king, two-king variants simply start with two of them
     King,
+    /// Moves and captures like `Normal`, and may also jump over an adjacent piece of its own
+    /// side onto the empty square behind it
+    Commander(Player),
+    /// Moves and captures like `Normal`, and may also jump over an adjacent enemy piece other
+    /// than the king onto the empty square behind it, taking that piece
+    Knight(Player),
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -35,7 +135,7 @@ pub enum Player {
 
 pub trait Hnfen: Sized {
     fn as_hnfen(&self) -> String;
-    fn from_hnfen(hnfen: &str) -> Option<Self>;
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError>;
 }
 
 impl Hnfen for Player {
@@ -47,11 +147,14 @@ impl Hnfen for Player {
         .to_string()
     }
 
-    fn from_hnfen(hnfen: &str) -> Option<Self> {
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
         match hnfen {
-            BLACK => Some(Player::Black),
-            WHITE => Some(Player::White),
-            _ => None,
+            BLACK => Ok(Player::Black),
+            WHITE => Ok(Player::White),
+            _ => Err(ParseError::new(
+                0,
+                ParseErrorKind::BadSideToMove(hnfen.to_string()),
+            )),
         }
     }
 }
@@ -71,16 +174,31 @@ impl Hnfen for Piece {
             Piece::Normal(Player::Black) => BLACK,
             Piece::Normal(Player::White) => WHITE,
             Piece::King => KING,
+            Piece::Commander(Player::Black) => BLACK_COMMANDER,
+            Piece::Commander(Player::White) => WHITE_COMMANDER,
+            Piece::Knight(Player::Black) => BLACK_KNIGHT,
+            Piece::Knight(Player::White) => WHITE_KNIGHT,
         }
         .to_owned()
     }
 
-    fn from_hnfen(hnfen: &str) -> Option<Self> {
-        Some(match hnfen {
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
+        let mut chars = hnfen.char_indices();
+        let (_, c) = chars
+            .next()
+            .ok_or_else(|| ParseError::new(0, ParseErrorKind::Empty))?;
+        if let Some((offset, extra)) = chars.next() {
+            return Err(ParseError::new(offset, ParseErrorKind::BadCharacter(extra)));
+        }
+        Ok(match hnfen {
             BLACK => Piece::Normal(Player::Black),
             WHITE => Piece::Normal(Player::White),
             KING => Piece::King,
-            _ => return None,
+            BLACK_COMMANDER => Piece::Commander(Player::Black),
+            WHITE_COMMANDER => Piece::Commander(Player::White),
+            BLACK_KNIGHT => Piece::Knight(Player::Black),
+            WHITE_KNIGHT => Piece::Knight(Player::White),
+            _ => return Err(ParseError::new(0, ParseErrorKind::BadCharacter(c))),
         })
     }
 }
@@ -88,10 +206,20 @@ impl Hnfen for Piece {
 impl Piece {
     pub fn color(&self) -> Player {
         match self {
-            Piece::Normal(c) => *c,
+            Piece::Normal(c) | Piece::Commander(c) | Piece::Knight(c) => *c,
             Piece::King => Player::White,
         }
     }
+
+    /// Whether this piece may jump over `other` standing next to it, see `Commander` and
+    /// `Knight`
+    pub fn may_jump_over(&self, other: Piece) -> bool {
+        match self {
+            Piece::Commander(c) => other.color() == *c,
+            Piece::Knight(c) => other != Piece::King && other.color() != *c,
+            _ => false,
+        }
+    }
 }
 
 impl Hnfen for Rank {
@@ -115,58 +243,58 @@ impl Hnfen for Rank {
         buf
     }
 
-    fn from_hnfen(hnfen: &str) -> Option<Self> {
-        // NOTE this is when I realized that using multi-digit numbers makes the language context-sensitive.
-        let mut rank = Rank { fields: [None; 11] };
-
-        enum C {
-            Number(usize),
-            Character(Piece),
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
+        let rank = Rank::from_hnfen_sized(hnfen, MAX_SIZE)?;
+        if rank.fields.is_empty() {
+            return Err(ParseError::new(0, ParseErrorKind::Empty));
         }
+        Ok(rank)
+    }
+}
 
-        let mut groups: Vec<C> = Vec::new();
+impl Rank {
+    /// Parses a rank of at most `size` fields
+    fn from_hnfen_sized(hnfen: &str, size: usize) -> Result<Self, ParseError> {
+        // NOTE this is when I realized that using multi-digit numbers makes the language context-sensitive.
+        let mut rank = Rank::default();
 
-        for k in hnfen.chars() {
-            match k {
-                _ if k.is_numeric() => {
-                    if let Some(C::Number(c)) = groups.last_mut() {
-                        *c = *c * 10 + k.to_digit(10).unwrap() as usize
-                    } else {
-                        groups.push(C::Number(k.to_digit(10).unwrap() as usize))
-                    }
-                }
-                _ => groups.push(C::Character(Piece::from_hnfen(&k.to_string())?)),
-            };
-        }
+        // Start offset and value of the number currently being read
+        let mut number: Option<(usize, usize)> = None;
+        let mut c_index: usize = 0;
 
-        let mut c_index = 0;
-        for group in groups.into_iter() {
-            match group {
-                C::Number(k) => {
-                    c_index += k;
-                }
-                C::Character(p) => {
-                    rank.fields[c_index] = Some(p);
-                    c_index += 1;
+        let skip = |c_index: &mut usize, number: &mut Option<(usize, usize)>| {
+            if let Some((start, k)) = number.take() {
+                *c_index = c_index.saturating_add(k);
+                if *c_index > size {
+                    return Err(ParseError::new(start, ParseErrorKind::RankOverflow));
                 }
             }
-        }
+            Ok(())
+        };
 
-        if c_index != 11 {
-            None
-        } else {
-            Some(rank)
+        for (offset, k) in hnfen.char_indices() {
+            if let Some(digit) = k.to_digit(10) {
+                let (start, value) = number.unwrap_or((offset, 0));
+                number = Some((
+                    start,
+                    value.saturating_mul(10).saturating_add(digit as usize),
+                ));
+                continue;
+            }
+            skip(&mut c_index, &mut number)?;
+            let piece = Piece::from_hnfen(&k.to_string()).map_err(|e| e.shifted(offset))?;
+            if c_index >= size {
+                return Err(ParseError::new(offset, ParseErrorKind::RankOverflow));
+            }
+            rank.fields.resize(c_index, None);
+            rank.fields.push(Some(piece));
+            c_index += 1;
         }
+        skip(&mut c_index, &mut number)?;
+        rank.fields.resize(c_index, None);
+        Ok(rank)
     }
-}
-
-impl Default for Rank {
-    fn default() -> Self {
-        Rank { fields: [None; 11] }
-    }
-}
 
-impl Rank {
     pub fn pretty(&self) -> String {
         let mut buf = String::new();
         for f in self.fields.iter() {
@@ -180,24 +308,136 @@ impl Rank {
 }
 
 impl Board {
+    /// Parses hnfen with `rules` for boards that don't name a ruleset. Besides the ranks and
+    /// the side to move, extended hnfen may give the ply, the plies since the last capture, the
+    /// name of the ruleset or `-`, and the dimensions, like
+    /// `3aaaaa3/.../3aaaaa3 h 13 2 fetlar 11x11`. Trailing fields can be left out. Boards are
+    /// square with an odd size up to `MAX_SIZE`, given by the number of ranks.
+    pub fn from_hnfen_with(hnfen: &str, rules: Ruleset) -> Result<Self, ParseError> {
+        let offset_of = |token: &str| token.as_ptr() as usize - hnfen.as_ptr() as usize;
+        let mut splits = hnfen.split_whitespace();
+
+        let ranks_token = splits
+            .next()
+            .ok_or_else(|| ParseError::new(hnfen.len(), ParseErrorKind::Empty))?;
+        let size = ranks_token.split(RANK_SEP).count();
+        if size % 2 == 0 || !(MIN_SIZE..=MAX_SIZE).contains(&size) {
+            return Err(ParseError::new(
+                offset_of(ranks_token),
+                ParseErrorKind::WrongRankCount(size),
+            ));
+        }
+        let ranks = ranks_token
+            .split(RANK_SEP)
+            .map(|r| {
+                let rank = Rank::from_hnfen_sized(r, size).map_err(|e| e.shifted(offset_of(r)))?;
+                if rank.fields.len() < size {
+                    return Err(ParseError::new(
+                        offset_of(r) + r.len(),
+                        ParseErrorKind::RankUnderflow(rank.fields.len()),
+                    ));
+                }
+                Ok(rank)
+            })
+            .collect::<Result<Vec<Rank>, ParseError>>()?;
+
+        let next = if let Some(s) = splits.next() {
+            Player::from_hnfen(s).map_err(|e| e.shifted(offset_of(s)))?
+        } else {
+            rules.first
+        };
+
+        // Optional fields of the extended syntax
+        let number = |s: &str| {
+            s.parse::<u32>().map_err(|_| {
+                let (offset, c) = s
+                    .char_indices()
+                    .find(|(_, c)| !c.is_ascii_digit())
+                    .unwrap_or((0, '0'));
+                match c {
+                    '0' => ParseError::new(offset_of(s), ParseErrorKind::BadNumber(s.to_string())),
+                    c => ParseError::new(offset_of(s) + offset, ParseErrorKind::BadCharacter(c)),
+                }
+            })
+        };
+        let ply = splits.next().map(number).transpose()?.unwrap_or(0);
+        let since_capture = splits.next().map(number).transpose()?.unwrap_or(0);
+        let rules = match splits.next() {
+            Some(UNNAMED_RULES) | None => rules,
+            Some(name) => Ruleset::from_name(name).ok_or_else(|| {
+                ParseError::new(
+                    offset_of(name),
+                    ParseErrorKind::UnknownRuleset(name.to_string()),
+                )
+            })?,
+        };
+        if let Some(s) = splits.next() {
+            if s != format!("{0}x{0}", size) {
+                return Err(ParseError::new(
+                    offset_of(s),
+                    ParseErrorKind::BadDimensions(s.to_string()),
+                ));
+            }
+        }
+
+        if let Some(s) = splits.next() {
+            return Err(ParseError::new(
+                offset_of(s),
+                ParseErrorKind::UnexpectedToken(s.to_string()),
+            ));
+        }
+
+        let mut board = Board {
+            ranks,
+            next,
+            rules,
+            ply,
+            since_capture,
+            zobrist_pieces: 0,
+        };
+        board.refresh_zobrist();
+        Ok(board)
+    }
+
+    /// Number of ranks, and of fields per rank
+    pub fn size(&self) -> usize {
+        self.ranks.len()
+    }
+
     pub fn get(&self, pos: &Position) -> Option<Piece> {
-        let (x, y) = pos.to_indices();
+        let (x, y) = pos.to_sized_indices(self.size());
         self.ranks[y].fields[x]
     }
 
     pub fn set(&mut self, pos: &Position, piece: &Option<Piece>) {
-        let (x, y) = pos.to_indices();
+        let (x, y) = pos.to_sized_indices(self.size());
+        for p in [self.ranks[y].fields[x], *piece].iter().flatten() {
+            self.zobrist_pieces ^= zobrist::piece_key(*p, pos);
+        }
         self.ranks[y].fields[x] = *piece;
     }
 
+    /// Zobrist key of the pieces and the side to move, like `zobrist::zobrist_key` but
+    /// without going over the board
+    pub fn zobrist(&self) -> u64 {
+        match self.next {
+            Player::Black => self.zobrist_pieces,
+            Player::White => self.zobrist_pieces ^ zobrist::WHITE_TO_MOVE,
+        }
+    }
+
+    /// Recomputes the key `zobrist` returns, needed after changing `ranks` directly
+    pub fn refresh_zobrist(&mut self) {
+        self.zobrist_pieces = zobrist::pieces_key(self);
+    }
+
     pub fn pieces(&self, color: Player) -> Vec<Position> {
         let mut pos = Vec::new();
         for (y, rank) in self.ranks.iter().enumerate() {
             for (x, piece) in rank.fields.iter().enumerate() {
                 match piece {
-                    Some(Piece::Normal(c)) if *c == color => pos.push(Position::from_indices(x, y)),
-                    Some(Piece::King) if color == Player::White => {
-                        pos.push(Position::from_indices(x, y))
+                    Some(p) if p.color() == color => {
+                        pos.push(Position::from_sized_indices(x, y, self.size()))
                     }
                     _ => {}
                 }
@@ -206,124 +446,300 @@ impl Board {
         pos
     }
 
+    /// The first king found, attackers win once there is none left
     pub fn king(&self) -> Option<Position> {
+        self.kings().into_iter().next()
+    }
+
+    /// Every king on the board, two-king variants have more than one
+    pub fn kings(&self) -> Vec<Position> {
+        let mut pos = Vec::new();
         for (y, rank) in self.ranks.iter().enumerate() {
             for (x, piece) in rank.fields.iter().enumerate() {
                 if let Some(Piece::King) = piece {
-                    return Some(Position::from_indices(x, y));
+                    pos.push(Position::from_sized_indices(x, y, self.size()));
                 }
             }
         }
-        None
+        pos
     }
 
+    /// Whether any king stands on an escape square
     pub fn king_escaped(&self) -> bool {
-        if let Some(pos) = self.king() {
-            let (x, y) = pos.to_indices();
-            is_corner(x, y)
-        } else {
-            false
-        }
+        self.kings().iter().any(|pos| {
+            let (x, y) = pos.to_sized_indices(self.size());
+            self.rules.is_escape(x, y, self.size())
+        })
     }
 
-    /// Returns true if a king at position pos *would* be captured
-    pub fn is_king_capture(&self, pos: &Position) -> bool {
+    /// Returns true if a king at position pos *would* be captured by a piece moving next to it
+    /// in direction `dir`. Two sides only capture on the axis of that move.
+    pub fn is_king_capture(&self, pos: &Position, dir: &Direction) -> bool {
         //println!("Potential king capture with board\n{}", self.pretty());
 
-        let pos = pos.to_indices();
-        for dir in Direction::card().iter() {
+        let pos = pos.to_sized_indices(self.size());
+        let mut near_throne = is_throne(pos.0, pos.1, self.size());
+        // Whether the king is enclosed from each of Direction::card()
+        let mut hostile = [false; 4];
+        for (k, dir) in Direction::card().iter().enumerate() {
             let dir_diff = dir.vector(1);
             let check_place = (pos.0 as isize + dir_diff.0, pos.1 as isize + dir_diff.1);
 
-            if !in_board(check_place.0, check_place.1) {
-                return false; // not captured
+            if !in_board(check_place.0, check_place.1, self.size()) {
+                hostile[k] = self.rules.hostile_edge;
+                continue;
             }
 
             let check_place = (check_place.0 as usize, check_place.1 as usize);
-            if is_castle(check_place.0, check_place.1) {
-                continue; // captured in this direction
-            }
+            near_throne |= is_throne(check_place.0, check_place.1, self.size());
+            hostile[k] = match self.get(&Position::from_sized_indices(
+                check_place.0,
+                check_place.1,
+                self.size(),
+            )) {
+                Some(p) => p.color() == Player::Black,
+                None => self
+                    .rules
+                    .is_hostile_to_king(check_place.0, check_place.1, self.size()),
+            };
+        }
 
-            if let Some(p) = self.get(&Position::from_indices(check_place.0, check_place.1)) {
-                if p.color() == Player::White {
-                    return false; // not captured
-                } else {
-                    continue; // captured in this direction
-                }
-            } else {
-                return false; // not captured
-            }
+        let sides = if near_throne {
+            self.rules.king_capture_sides_near_throne
+        } else {
+            self.rules.king_capture_sides
+        };
+        match (sides, dir) {
+            // Up and Down, or Left and Right
+            (2, Direction::Up) | (2, Direction::Down) => hostile[0] && hostile[1],
+            (2, _) => hostile[2] && hostile[3],
+            (n, _) => hostile.iter().filter(|h| **h).count() >= n as usize,
         }
+    }
 
-        true
+    /// Like `apply`, but refuses moves that `possible_moves` would not generate
+    pub fn try_apply(&mut self, mov: &Move) -> Result<MoveOutcome, IllegalMove> {
+        check_move(self, mov)?;
+        Ok(self.apply(mov))
     }
 
-    pub fn apply(&mut self, mov: &Move) {
-        let (x, y) = mov.from.to_indices();
-        let piece = if let Some(p) = self.ranks[y].fields[x] {
+    pub fn apply(&mut self, mov: &Move) -> MoveOutcome {
+        let mut outcome = MoveOutcome {
+            mov: mov.clone(),
+            piece: None,
+            previous: self.next,
+            captured: Vec::new(),
+            king_captured: false,
+            king_escaped: false,
+            next: self.next,
+            since_capture: self.since_capture,
+        };
+        let piece = if let Some(p) = self.get(&mov.from) {
             p
         } else {
             // Probably a nop move
-            return;
+            return outcome;
         };
+        outcome.piece = Some(piece);
         let move_color = piece.color();
-        self.ranks[y].fields[x] = None;
-        let (x, y) = mov.to.to_indices();
-        self.ranks[y].fields[x] = Some(piece);
+        self.set(&mov.from, &None);
+        self.set(&mov.to, &Some(piece));
+        let (x, y) = mov.to.to_sized_indices(self.size());
+        if let Some((jumped_pos, jumped)) = self.jumped_over(mov, piece) {
+            // Knights take the piece they jump over
+            if let Piece::Knight(_) = piece {
+                self.set(&jumped_pos, &None);
+                outcome.captured.push((jumped_pos, jumped));
+            }
+        }
 
+        let disarmed = piece == Piece::King && !self.rules.king_armed.is_hammer();
         for dir in Direction::card().iter() {
+            if disarmed {
+                break;
+            }
             let dir_diff = dir.vector(1);
             let check_place = (x as isize + dir_diff.0, y as isize + dir_diff.1);
-            if !in_board(check_place.0, check_place.1) {
+            if !in_board(check_place.0, check_place.1, self.size()) {
                 continue;
             }
             let other_place = (check_place.0 as usize, check_place.1 as usize);
-            let other_is_king =
-                match self.get(&Position::from_indices(other_place.0, other_place.1)) {
-                    Some(Piece::Normal(c)) if c != move_color => {
-                        // Potential take of other_piece
-                        false
-                    }
-                    Some(Piece::King) if move_color == Player::Black => {
-                        // Potential take of king!
-                        true
-                    }
-                    _ => {
-                        // Nothing here to take, continue with next direction
-                        continue;
+            let other_pos = Position::from_sized_indices(other_place.0, other_place.1, self.size());
+            let other = match self.get(&other_pos) {
+                Some(p) if p != Piece::King && p.color() != move_color => {
+                    // Potential take of other_piece
+                    p
+                }
+                Some(Piece::King) if move_color == Player::Black => {
+                    // Potential take of king!
+                    if self.is_king_capture(&other_pos, dir) {
+                        // Took the king, that's pretty cool
+                        self.set(&other_pos, &None);
+                        outcome.captured.push((other_pos, Piece::King));
+                        outcome.king_captured = true;
                     }
-                };
+                    continue;
+                }
+                _ => {
+                    // Nothing here to take, continue with next direction
+                    continue;
+                }
+            };
             let opposite_place = (
                 other_place.0 as isize + dir_diff.0,
                 other_place.1 as isize + dir_diff.1,
             );
-            if !in_board(opposite_place.0, opposite_place.1) {
+            if !in_board(opposite_place.0, opposite_place.1, self.size()) {
                 continue;
             }
             let opposite_place = (opposite_place.0 as usize, opposite_place.1 as usize);
 
-            if other_is_king {
-                if self.is_king_capture(&Position::from_indices(other_place.0, other_place.1)) {
-                    // Took the king, that's pretty cool
-                    self.set(&Position::from_indices(other_place.0, other_place.1), &None);
-                } else {
-                    // Not taking the king
+            // Is surrounded by other piece of move_color, or a square hostile to it
+            let opposite_pos =
+                Position::from_sized_indices(opposite_place.0, opposite_place.1, self.size());
+            let surrounded = self.captures_for(&opposite_pos, move_color)
+                || self.is_hostile_to(&opposite_pos, other.color());
+            if surrounded {
+                self.set(&other_pos, &None);
+                outcome.captured.push((other_pos, other));
+            }
+        }
+        if self.rules.shieldwall && !disarmed {
+            self.capture_shieldwalls(x, y, move_color, &mut outcome);
+        }
+        self.next = move_color.opposite();
+        self.ply += 1;
+        self.since_capture = if outcome.captured.is_empty() {
+            self.since_capture + 1
+        } else {
+            0
+        };
+        outcome.king_escaped = piece == Piece::King && self.rules.is_escape(x, y, self.size());
+        outcome.next = self.next;
+        outcome
+    }
+
+    /// The piece that `piece` jumps over with `mov` and its square, if `mov` is a jump
+    pub fn jumped_over(&self, mov: &Move, piece: Piece) -> Option<(Position, Piece)> {
+        let (from_x, from_y) = mov.from.to_sized_indices(self.size());
+        let (to_x, to_y) = mov.to.to_sized_indices(self.size());
+        let distance = (
+            to_x as isize - from_x as isize,
+            to_y as isize - from_y as isize,
+        );
+        if !matches!(distance, (-2, 0) | (2, 0) | (0, -2) | (0, 2)) {
+            return None;
+        }
+        let middle =
+            Position::from_sized_indices((from_x + to_x) / 2, (from_y + to_y) / 2, self.size());
+        let jumped = self.get(&middle)?;
+        if piece.may_jump_over(jumped) {
+            Some((middle, jumped))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the piece on pos takes part in captures for color
+    fn captures_for(&self, pos: &Position, color: Player) -> bool {
+        match self.get(pos) {
+            Some(Piece::King) => color == Player::White && self.rules.king_armed.is_anvil(),
+            Some(p) => p.color() == color,
+            None => false,
+        }
+    }
+
+    /// Whether the square pos replaces a capturing piece against pieces of victim. The empty
+    /// throne is hostile to both sides, the occupied one only to attackers.
+    pub fn is_hostile_to(&self, pos: &Position, victim: Player) -> bool {
+        let (x, y) = pos.to_sized_indices(self.size());
+        self.rules.is_hostile(x, y, self.size())
+            && (self.get(pos).is_none() || is_throne(x, y, self.size()) && victim == Player::Black)
+    }
+
+    /// Captures rows of two or more pieces along the edge that the piece of `color` on (x, y)
+    /// brackets at one end, as long as every piece in the row is blocked from the front. The
+    /// king may be part of such a row, but is not taken.
+    fn capture_shieldwalls(
+        &mut self,
+        x: usize,
+        y: usize,
+        color: Player,
+        outcome: &mut MoveOutcome,
+    ) {
+        // Direction towards the middle of the board, and directions along the edge
+        let mut edges = Vec::new();
+        let last = self.size() - 1;
+        if x == 0 || x == last {
+            edges.push(((if x == 0 { 1 } else { -1 }, 0), [(0, -1), (0, 1)]));
+        }
+        if y == 0 || y == last {
+            edges.push(((0, if y == 0 { 1 } else { -1 }), [(-1, 0), (1, 0)]));
+        }
+
+        for (inward, alongs) in edges.into_iter() {
+            for along in alongs.iter() {
+                let mut row = Vec::new();
+                let mut k = 1;
+                let bracketed = loop {
+                    let (row_x, row_y) = (x as isize + along.0 * k, y as isize + along.1 * k);
+                    if !in_board(row_x, row_y, self.size()) {
+                        break false;
+                    }
+                    let row_pos =
+                        Position::from_sized_indices(row_x as usize, row_y as usize, self.size());
+                    match self.get(&row_pos) {
+                        Some(p) if p.color() != color => {
+                            let front = Position::from_sized_indices(
+                                (row_x + inward.0) as usize,
+                                (row_y + inward.1) as usize,
+                                self.size(),
+                            );
+                            if !self.captures_for(&front, color) {
+                                break false;
+                            }
+                            row.push((row_pos, p));
+                        }
+                        Some(_) => break self.captures_for(&row_pos, color),
+                        None => {
+                            break self
+                                .rules
+                                .is_hostile(row_x as usize, row_y as usize, last + 1)
+                        }
+                    }
+                    k += 1;
+                };
+                if !bracketed || row.len() < 2 {
                     continue;
                 }
-            } else if let Some(p) =
-                self.get(&Position::from_indices(opposite_place.0, opposite_place.1))
-            {
-                // Is surrounded by other piece of move_color
-                if p.color() == move_color {
-                    self.set(&Position::from_indices(other_place.0, other_place.1), &None);
+                for (pos, p) in row.into_iter() {
+                    if p != Piece::King {
+                        self.set(&pos, &None);
+                        outcome.captured.push((pos, p));
+                    }
                 }
             }
         }
-        self.next = move_color.opposite();
+    }
+
+    /// Takes back a move using the outcome `apply` returned for it. Outcomes have to be
+    /// unapplied in reverse order, and only moves accepted by `try_apply` are restored exactly.
+    pub fn unapply(&mut self, outcome: &MoveOutcome) {
+        if let Some(piece) = outcome.piece {
+            self.set(&outcome.mov.to, &None);
+            self.set(&outcome.mov.from, &Some(piece));
+            self.ply -= 1;
+        }
+        for (pos, piece) in outcome.captured.iter() {
+            self.set(pos, &Some(*piece));
+        }
+        self.next = outcome.previous;
+        self.since_capture = outcome.since_capture;
     }
 
     pub fn pretty(&self) -> String {
-        let mut pp = "╔═══════════╗\n".to_string();
+        let border = "═".repeat(self.size());
+        let mut pp = format!("╔{}╗\n", border);
         pp.push_str(
             &self
                 .ranks
@@ -332,7 +748,7 @@ impl Board {
                 .collect::<Vec<String>>()
                 .join("\n"),
         );
-        pp.push_str("\n╚═══════════╝");
+        pp.push_str(&format!("\n╚{}╝", border));
         pp
     }
 }
@@ -350,24 +766,34 @@ impl Hnfen for Board {
         );
         buf.push(' ');
         buf.push_str(&self.next.as_hnfen());
+
+        // Extended fields, up to the last one that differs from its default
+        let dimensions = if self.size() != DEFAULT_SIZE {
+            Some(format!("{0}x{0}", self.size()))
+        } else {
+            None
+        };
+        let rules = match self.rules.name() {
+            _ if self.rules == Ruleset::default() && dimensions.is_none() => None,
+            Some(name) => Some(name),
+            None => Some(UNNAMED_RULES),
+        };
+        if rules.is_some() || self.ply != 0 || self.since_capture != 0 {
+            buf.push_str(&format!(" {} {}", self.ply, self.since_capture));
+        }
+        if let Some(name) = rules {
+            buf.push(' ');
+            buf.push_str(name);
+        }
+        if let Some(dimensions) = dimensions {
+            buf.push(' ');
+            buf.push_str(&dimensions);
+        }
         buf
     }
 
-    fn from_hnfen(hnfen: &str) -> Option<Self> {
-        let splits: Vec<&str> = hnfen.split_whitespace().collect();
-        Some(Board {
-            ranks: splits[0]
-                .split(RANK_SEP)
-                .map(Rank::from_hnfen)
-                .collect::<Option<Vec<Rank>>>()?
-                .try_into()
-                .unwrap(),
-            next: if let Some(s) = splits.get(1) {
-                Player::from_hnfen(s)?
-            } else {
-                Player::Black
-            },
-        })
+    fn from_hnfen(hnfen: &str) -> Result<Self, ParseError> {
+        Board::from_hnfen_with(hnfen, Ruleset::default())
     }
 }
 
@@ -381,6 +807,7 @@ impl Default for Board {
 mod tests {
 
     use super::*;
+    use crate::moves::possible_moves;
 
     #[test]
     fn default_board() {
@@ -389,7 +816,9 @@ mod tests {
 
     #[test]
     fn test_rank_to_hnfen() {
-        let mut rank = Rank { fields: [None; 11] };
+        let mut rank = Rank {
+            fields: vec![None; 11],
+        };
         assert_eq!(rank.as_hnfen(), "11");
         rank.fields[10] = Some(Piece::King);
         assert_eq!(rank.as_hnfen(), "10K");
@@ -427,10 +856,244 @@ mod tests {
         );
     }
 
+    #[test]
+    fn extended_hnfen() {
+        let start = crate::DEFAULT_START_HNFEN;
+        let board = Board::from_hnfen(&format!("{} 0 0 copenhagen 11x11", start)).unwrap();
+        assert_eq!(board, Board::default());
+        assert_eq!(board.as_hnfen(), start);
+
+        let board = Board::from_hnfen(&format!("{} 13 2", start)).unwrap();
+        assert_eq!((board.ply, board.since_capture), (13, 2));
+        assert_eq!(board.as_hnfen(), format!("{} 13 2", start));
+
+        let board = Board::from_hnfen(&format!("{} 0 0 fetlar", start)).unwrap();
+        assert_eq!(board.rules, Ruleset::fetlar());
+        assert_eq!(board.as_hnfen(), format!("{} 0 0 fetlar", start));
+
+        let rules = Ruleset {
+            hostile_edge: true,
+            ..Ruleset::default()
+        };
+        let board = Board::from_hnfen_with(&format!("{} 4 1 -", start), rules.clone()).unwrap();
+        assert_eq!(board.rules, rules);
+        assert_eq!(board.as_hnfen(), format!("{} 4 1 -", start));
+    }
+
+    #[test]
+    fn sizes() {
+        let board = Board::from_hnfen(&vec!["13"; 13].join("/")).unwrap();
+        assert_eq!(board.size(), 13);
+        assert_eq!(
+            board.as_hnfen(),
+            format!("{} a 0 0 copenhagen 13x13", vec!["13"; 13].join("/"))
+        );
+
+        let mut ranks = vec!["19"; 19];
+        ranks[9] = "9K9";
+        let board = Board::from_hnfen(&format!("{} h", ranks.join("/"))).unwrap();
+        assert_eq!(board.king(), Some(Position::from_hnfen("j10").unwrap()));
+        assert_eq!(possible_moves(&board).len(), 4 * 9);
+        assert!(board.pretty().starts_with("╔═══════════════════╗"));
+
+        assert_eq!(
+            Board::from_hnfen("7/7/7/7/7/7/7 a 0 0 - 11x11"),
+            Err(ParseError::new(
+                22,
+                ParseErrorKind::BadDimensions("11x11".into())
+            ))
+        );
+        assert_eq!(
+            Board::from_hnfen(&vec!["21"; 21].join("/")),
+            Err(ParseError::new(0, ParseErrorKind::WrongRankCount(21)))
+        );
+    }
+
+    #[test]
+    fn counters() {
+        let mut board = Board::from_hnfen("11/11/11/11/11/10h/11/11/1a9/1h9/3a7").unwrap();
+        let outcome = board.apply(&Move::from_hnfen("d1c1").unwrap());
+        assert_eq!((board.ply, board.since_capture), (1, 1));
+        board.unapply(&outcome);
+        assert_eq!((board.ply, board.since_capture), (0, 0));
+
+        board.apply(&Move::from_hnfen("d1c1").unwrap());
+        board.apply(&Move::from_hnfen("k6k7").unwrap());
+        board.apply(&Move::from_hnfen("c1b1").unwrap());
+        assert_eq!((board.ply, board.since_capture), (3, 0));
+        assert!(board.as_hnfen().ends_with(" h 3 0"));
+    }
+
+    #[test]
+    fn try_apply() {
+        let mut board = Board::default();
+        let mov = Move::from_hnfen("f5f4").unwrap();
+        assert_eq!(board.try_apply(&mov), Err(IllegalMove::WrongSide));
+        assert_eq!(board, Board::default());
+
+        let mov = Move::from_hnfen("d11d9").unwrap();
+        assert!(board.try_apply(&mov).unwrap().captured.is_empty());
+        assert_eq!(board.next, Player::White);
+        assert_eq!(board.get(&mov.to), Some(Piece::Normal(Player::Black)));
+    }
+
+    #[test]
+    fn try_apply_untrusted() {
+        // Moves from a client skip the hnfen parser and its coordinate checks
+        let mut board = Board::default();
+        for json in &[
+            r#"{"from":{"column":"A","rank":8},"to":{"column":"a","rank":9}}"#,
+            r#"{"from":{"column":"d","rank":11},"to":{"column":"\u0000","rank":11}}"#,
+            r#"{"from":{"column":"d","rank":0},"to":{"column":"d","rank":9}}"#,
+            r#"{"from":{"column":"d","rank":11},"to":{"column":"l","rank":11}}"#,
+        ] {
+            let mov: Move = serde_json::from_str(json).unwrap();
+            assert_eq!(board.try_apply(&mov), Err(IllegalMove::OffBoard));
+        }
+        assert_eq!(board, Board::default());
+    }
+
+    #[test]
+    fn deserialize_untrusted() {
+        let json = |widths: &[usize]| {
+            let ranks: Vec<String> = widths
+                .iter()
+                .map(|w| format!(r#"{{"fields":[{}]}}"#, vec!["null"; *w].join(",")))
+                .collect();
+            format!(r#"{{"ranks":[{}],"next":"Black"}}"#, ranks.join(","))
+        };
+        let read = |json: &str| serde_json::from_str::<Board>(json).map_err(|e| e.to_string());
+        assert!(read(&json(&[21; 21]))
+            .unwrap_err()
+            .contains("can't have 21 ranks"));
+        assert!(read(&json(&[21]))
+            .unwrap_err()
+            .contains("can't have 1 ranks"));
+        assert!(read(&json(&[5, 5, 21, 5, 5]))
+            .unwrap_err()
+            .contains("too many fields"));
+        assert!(read(&json(&[5, 5, 4, 5, 5]))
+            .unwrap_err()
+            .contains("only 4 fields"));
+        let board = read(&json(&[5; 5])).unwrap();
+        assert_eq!(board, Board::from_hnfen("5/5/5/5/5 a").unwrap());
+        assert_eq!(board.zobrist(), 0);
+    }
+
+    #[test]
+    fn apply_outcome() {
+        let mut board = Board::from_hnfen("11/11/11/11/11/11/11/11/1a9/1h9/3a7").unwrap();
+        let outcome = board.apply(&Move::from_hnfen("d1b1").unwrap());
+        assert_eq!(
+            outcome,
+            MoveOutcome {
+                mov: Move::from_hnfen("d1b1").unwrap(),
+                piece: Some(Piece::Normal(Player::Black)),
+                previous: Player::Black,
+                captured: vec![(Position::from_indices(1, 9), Piece::Normal(Player::White))],
+                king_captured: false,
+                king_escaped: false,
+                next: Player::White,
+                since_capture: 0,
+            }
+        );
+        assert_eq!(board.get(&outcome.captured[0].0), None);
+
+        let mut board = Board::from_hnfen("11/11/11/11/11/4a6/3aK1a4/4a6/11/11/11").unwrap();
+        let outcome = board.apply(&Move::from_hnfen("g5f5").unwrap());
+        assert!(outcome.king_captured);
+        assert_eq!(board.king(), None);
+
+        let mut board = Board::from_hnfen("11/11/11/11/11/11/11/11/11/11/3K7 h").unwrap();
+        let outcome = board.apply(&Move::from_hnfen("d1a1").unwrap());
+        assert!(outcome.king_escaped);
+        assert!(outcome.captured.is_empty());
+        assert_eq!(outcome.next, Player::Black);
+    }
+
+    #[test]
+    fn unapply() {
+        let start = Board::from_hnfen("11/11/11/11/11/4a6/3aK1a4/4a6/1a9/1h9/3a7").unwrap();
+        let mut board = start.clone();
+        let outcomes: Vec<MoveOutcome> = ["g5f5", "d1b1", "a11a11"]
+            .iter()
+            .map(|m| board.apply(&Move::from_hnfen(m).unwrap()))
+            .collect();
+        assert_eq!(board.king(), None);
+        assert_eq!(board.pieces(Player::White).len(), 0);
+        for outcome in outcomes.iter().rev() {
+            board.unapply(outcome);
+        }
+        assert_eq!(board, start);
+
+        let mut board = Board::default();
+        for mov in possible_moves(&Board::default()) {
+            let outcome = board.apply(&mov);
+            assert_ne!(board, Board::default());
+            board.unapply(&outcome);
+            assert_eq!(board, Board::default());
+        }
+    }
+
     #[test]
     fn get_pieces_amount() {
         let board = Board::default();
         assert_eq!(board.pieces(Player::White).len(), 13);
         assert_eq!(board.pieces(Player::Black).len(), 24);
     }
+
+    #[test]
+    fn piece_types() {
+        let rank = Rank::from_hnfen("cCnNK1ah3").unwrap();
+        assert_eq!(rank.fields[0], Some(Piece::Commander(Player::Black)));
+        assert_eq!(rank.fields[3], Some(Piece::Knight(Player::White)));
+        assert_eq!(rank.as_hnfen(), "cCnNK1ah3");
+
+        let mov = |hnfen: &str| Move::from_hnfen(hnfen).unwrap();
+        let board = |middle: &str, jumper: &str| {
+            let hnfen = format!("11/11/11/11/11/{}/{}/11/11/11/1K9 a", middle, jumper);
+            Board::from_hnfen(&hnfen).unwrap()
+        };
+        // Knights jump over enemies other than the king, taking them
+        let mut knight = board("5h5", "5n5");
+        assert!(possible_moves(&knight).contains(&mov("f5f7")));
+        let before = knight.clone();
+        let outcome = knight.try_apply(&mov("f5f7")).unwrap();
+        let jumped = Position::from_hnfen("f6").unwrap();
+        assert_eq!(
+            outcome.captured,
+            vec![(jumped, Piece::Normal(Player::White))]
+        );
+        knight.unapply(&outcome);
+        assert_eq!(knight, before);
+        let blocked = Err(IllegalMove::Blocked);
+        assert_eq!(check_move(&board("5K5", "5n5"), &mov("f5f7")), blocked);
+        assert_eq!(check_move(&board("5a5", "5n5"), &mov("f5f7")), blocked);
+        // Commanders jump over their own pieces
+        let mut commander = board("5a5", "5c5");
+        assert!(possible_moves(&commander).contains(&mov("f5f7")));
+        assert!(commander
+            .try_apply(&mov("f5f7"))
+            .unwrap()
+            .captured
+            .is_empty());
+        assert_eq!(check_move(&board("5h5", "5c5"), &mov("f5f7")), blocked);
+        // Otherwise both capture like ordinary pieces
+        let mut board = Board::from_hnfen("11/11/11/11/11/11/11/3nh4a1/11/11/1K9 a").unwrap();
+        assert_eq!(board.apply(&mov("j4f4")).captured.len(), 1);
+
+        // Attackers have to take every king, one escaping is enough for the defenders
+        let rules = Ruleset {
+            king_capture_sides: 2,
+            ..Ruleset::default()
+        };
+        let hnfen = "11/11/11/11/11/11/11/11/2aK1a2K2/11/11 a";
+        let mut board = Board::from_hnfen_with(hnfen, rules).unwrap();
+        assert_eq!(board.kings().len(), 2);
+        assert!(board.apply(&mov("f3e3")).king_captured);
+        assert_eq!(board.kings(), vec![Position::from_hnfen("i3").unwrap()]);
+        assert!(!board.status().is_over());
+        let board = Board::from_hnfen("K10/11/11/11/11/11/11/11/11/a10/5K5 h").unwrap();
+        assert!(board.king_escaped());
+    }
 }
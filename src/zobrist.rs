@@ -0,0 +1,152 @@
+use crate::moves::Position;
+use crate::types::{Board, Piece, Player, MAX_SIZE};
+
+const SQUARES: usize = MAX_SIZE * MAX_SIZE;
+const PIECE_KINDS: usize = 7;
+
+/// Key for every kind of piece on every square, the same for all board sizes
+static KEYS: [[u64; SQUARES]; PIECE_KINDS] = piece_keys();
+
+/// Key added while white is to move
+pub const WHITE_TO_MOVE: u64 = mix(SQUARES * PIECE_KINDS);
+
+/// Scrambles `index` into a well distributed key, using the finalizer of splitmix64
+const fn mix(index: usize) -> u64 {
+    let mut z = (index as u64 + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+const fn piece_keys() -> [[u64; SQUARES]; PIECE_KINDS] {
+    let mut keys = [[0; SQUARES]; PIECE_KINDS];
+    let mut kind = 0;
+    while kind < PIECE_KINDS {
+        let mut square = 0;
+        while square < SQUARES {
+            keys[kind][square] = mix(kind * SQUARES + square);
+            square += 1;
+        }
+        kind += 1;
+    }
+    keys
+}
+
+/// Key of `piece` standing on `pos`
+pub fn piece_key(piece: Piece, pos: &Position) -> u64 {
+    let kind = match piece {
+        Piece::Normal(Player::Black) => 0,
+        Piece::Normal(Player::White) => 1,
+        Piece::King => 2,
+        Piece::Commander(Player::Black) => 3,
+        Piece::Commander(Player::White) => 4,
+        Piece::Knight(Player::Black) => 5,
+        Piece::Knight(Player::White) => 6,
+    };
+    let (x, y) = pos.to_sized_indices(MAX_SIZE);
+    KEYS[kind][x + y * MAX_SIZE]
+}
+
+/// Zobrist key of the pieces on the board and the side to move, computed from scratch.
+/// `Board::zobrist` returns the same key, but keeps it up to date move by move.
+pub fn zobrist_key(board: &Board) -> u64 {
+    let mut key = pieces_key(board);
+    if board.next == Player::White {
+        key ^= WHITE_TO_MOVE;
+    }
+    key
+}
+
+/// Zobrist key of the pieces alone
+pub(crate) fn pieces_key(board: &Board) -> u64 {
+    let mut key = 0;
+    for (y, rank) in board.ranks.iter().enumerate() {
+        for (x, piece) in rank.fields.iter().enumerate() {
+            if let Some(piece) = piece {
+                key ^= piece_key(*piece, &Position::from_sized_indices(x, y, board.size()));
+            }
+        }
+    }
+    key
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::moves::possible_moves;
+    use crate::types::Hnfen;
+
+    #[test]
+    fn incremental() {
+        let mut board = Board::default();
+        let start = zobrist_key(&board);
+        assert_eq!(board.zobrist(), start);
+        let mut outcomes = Vec::new();
+        for ply in 0..200 {
+            let moves = possible_moves(&board);
+            if moves.is_empty() || board.status().is_over() {
+                break;
+            }
+            outcomes.push(board.apply(&moves[ply * 7 % moves.len()]));
+            assert_eq!(board.zobrist(), zobrist_key(&board));
+        }
+        assert!(outcomes.iter().any(|outcome| !outcome.captured.is_empty()));
+        for outcome in outcomes.iter().rev() {
+            board.unapply(outcome);
+            assert_eq!(board.zobrist(), zobrist_key(&board));
+        }
+        assert_eq!(board.zobrist(), start);
+    }
+
+    #[test]
+    fn distinguishes() {
+        let board = Board::default();
+        let mut white = board.clone();
+        white.next = Player::White;
+        assert_ne!(board.zobrist(), white.zobrist());
+        assert_eq!(white.zobrist() ^ WHITE_TO_MOVE, board.zobrist());
+
+        let hnfen = |s: &str| Board::from_hnfen(s).unwrap().zobrist();
+        assert_ne!(
+            hnfen("11/11/11/11/11/5K5/11/11/11/11/1a9 a"),
+            hnfen("11/11/11/11/11/5K5/11/11/11/11/a10 a")
+        );
+        assert_ne!(
+            hnfen("11/11/11/11/11/5K5/11/11/11/11/1a9 a"),
+            hnfen("11/11/11/11/11/5K5/11/11/11/11/1n9 a")
+        );
+    }
+
+    #[test]
+    fn not_serialized() {
+        let mut board = Board::default();
+        board.apply(&possible_moves(&board)[0]);
+        let json = serde_json::to_string(&board).unwrap();
+        assert!(!json.contains("zobrist"));
+        let read: Board = serde_json::from_str(&json).unwrap();
+        assert_eq!(read, board);
+        assert_eq!(read.zobrist(), zobrist_key(&board));
+
+        let json = r#"{"ranks":[{"fields":[null,null,null,null,null]},{"fields":[null,null,null,null,null]},{"fields":[null,null,"King",null,null]},{"fields":[null,null,null,null,null]},{"fields":[null,null,null,null,null]}],"next":"White"}"#;
+        let read: Board = serde_json::from_str(json).unwrap();
+        assert_ne!(read.zobrist(), WHITE_TO_MOVE);
+        assert_eq!(read.zobrist(), zobrist_key(&read));
+    }
+
+    #[test]
+    fn not_compared() {
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::{Hash, Hasher};
+        let hash = |board: &Board| {
+            let mut hasher = DefaultHasher::new();
+            board.hash(&mut hasher);
+            hasher.finish()
+        };
+        let board = Board::default();
+        let mut stale = board.clone();
+        stale.zobrist_pieces = 0;
+        assert_eq!(stale, board);
+        assert_eq!(hash(&stale), hash(&board));
+    }
+}